@@ -0,0 +1,333 @@
+//! Scanning of the members of gzip, zip and tar archives.
+//!
+//! Archives are unpacked recursively, so a `.tar.gz` attached in a zip file is scanned
+//! down to the files of the tarball. Each archive is scanned as is, and then each of its
+//! members, with results tagged with the path of the member inside the archive.
+//!
+//! Requires the `archive` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use yara::{Compiler, archive::ArchiveScanner};
+//! let rules = Compiler::new()?
+//!     .add_rules_str("rule contains_rust { strings: $rust = \"rust\" condition: $rust }")?
+//!     .compile_rules()?;
+//! let mut scanner = rules.scanner()?;
+//! let members = ArchiveScanner::new(&mut scanner)
+//!     .max_depth(3)
+//!     .max_member_size(64 * 1024 * 1024)
+//!     .scan_file("attachments.zip")?;
+//! for member in members {
+//!     match member.result {
+//!         Ok(rules) if !rules.is_empty() => println!("/{}: {} rules", member.path.join("/"), rules.len()),
+//!         Ok(_) => {}
+//!         Err(e) => eprintln!("/{}: {}", member.path.join("/"), e),
+//!     }
+//! }
+//! # Ok::<(), yara::Error>(())
+//! ```
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use crate::errors::*;
+use crate::options::ScanOptions;
+use crate::rules::OwnedRule;
+use crate::scanner::Scanner;
+
+/// Size under which the compression ratio of a member is not checked, as small members of
+/// repetitive data are legitimately highly compressed.
+const RATIO_MIN_SIZE: u64 = 1024 * 1024;
+
+/// The result of the scan of an archive or of one of its members.
+///
+/// Also used to report the errors while unpacking an archive, in which case `path` is
+/// the member that could not be unpacked, or the archive if the error is not specific
+/// to one of its members.
+#[derive(Debug)]
+pub struct ScannedMember {
+    /// Path of the member, from the outermost archive.
+    ///
+    /// Each element is the path of the member inside its parent archive, e.g.
+    /// `["docs.tar.gz", "docs.tar", "docs/report.txt"]` for a tarball in a zip file.
+    /// The content of a gzip stream is named after the file name of its header, or
+    /// empty. The outermost archive itself has an empty path.
+    pub path: Vec<String>,
+    pub result: Result<Vec<OwnedRule>, Error>,
+}
+
+/// Scanner of archives and of their members.
+///
+/// Members are fully decompressed in memory before being scanned, within the limits set
+/// to protect against decompression bombs. A member exceeding them is reported with an
+/// [`ArchiveError`] and is not scanned.
+pub struct ArchiveScanner<'s, 'rules> {
+    scanner: &'s mut Scanner<'rules>,
+    max_depth: usize,
+    max_member_size: u64,
+    max_total_size: u64,
+    max_ratio: u64,
+}
+
+impl<'s, 'rules> ArchiveScanner<'s, 'rules> {
+    pub fn new(scanner: &'s mut Scanner<'rules>) -> Self {
+        ArchiveScanner {
+            scanner,
+            max_depth: 5,
+            max_member_size: 128 * 1024 * 1024,
+            max_total_size: 1024 * 1024 * 1024,
+            max_ratio: 100,
+        }
+    }
+
+    /// Set how many levels of nested archives are unpacked.
+    ///
+    /// 0 only scans the outermost data. Defaults to 5.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the maximum decompressed size of a member, in bytes.
+    ///
+    /// Defaults to 128 MiB.
+    pub fn max_member_size(mut self, max_member_size: u64) -> Self {
+        self.max_member_size = max_member_size;
+        self
+    }
+
+    /// Set the maximum decompressed size of all the members, in bytes.
+    ///
+    /// Once reached, the unpacking stops. Defaults to 1 GiB.
+    pub fn max_total_size(mut self, max_total_size: u64) -> Self {
+        self.max_total_size = max_total_size;
+        self
+    }
+
+    /// Set the maximum ratio between the decompressed and the compressed size of a member.
+    ///
+    /// Only checked for members larger than 1 MiB. Defaults to 100.
+    pub fn max_ratio(mut self, max_ratio: u64) -> Self {
+        self.max_ratio = max_ratio;
+        self
+    }
+
+    /// Scan a file and, if it is an archive, its members.
+    pub fn scan_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<ScannedMember>, Error> {
+        let data = fs::read(path).map_err(|e| IoError::new(e, IoErrorKind::OpenScanFile))?;
+        Ok(self.scan_mem(&data))
+    }
+
+    /// Scan a buffer and, if it is an archive, its members.
+    pub fn scan_mem(&mut self, data: &[u8]) -> Vec<ScannedMember> {
+        let mut unpack = Unpack {
+            archive: self,
+            total_size: 0,
+            stopped: false,
+            members: Vec::new(),
+        };
+        unpack.scan(Vec::new(), data, 0);
+        unpack.members
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Format {
+    Gzip,
+    Zip,
+    Tar,
+}
+
+impl Format {
+    fn detect(data: &[u8]) -> Option<Format> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Format::Gzip)
+        } else if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+            Some(Format::Zip)
+        } else if data.get(257..262) == Some(b"ustar") {
+            Some(Format::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// State of the scan of an outermost archive.
+struct Unpack<'a, 's, 'rules> {
+    archive: &'a mut ArchiveScanner<'s, 'rules>,
+    total_size: u64,
+    /// Set once the maximum total size is reached.
+    stopped: bool,
+    members: Vec<ScannedMember>,
+}
+
+impl Unpack<'_, '_, '_> {
+    fn scan(&mut self, path: Vec<String>, data: &[u8], depth: usize) {
+        let result = self
+            .archive
+            .scanner
+            .scan_mem_owned(data, &ScanOptions::new())
+            .into_result();
+        self.members.push(ScannedMember {
+            path: path.clone(),
+            result,
+        });
+
+        if depth >= self.archive.max_depth {
+            return;
+        }
+        match Format::detect(data) {
+            Some(Format::Gzip) => self.unpack_gzip(path, data, depth + 1),
+            Some(Format::Zip) => self.unpack_zip(path, data, depth + 1),
+            Some(Format::Tar) => self.unpack_tar(path, data, depth + 1),
+            None => {}
+        }
+    }
+
+    fn unpack_gzip(&mut self, path: Vec<String>, data: &[u8], depth: usize) {
+        let mut decoder = match libflate::gzip::Decoder::new(data) {
+            Ok(decoder) => decoder,
+            Err(e) => return self.error(path, IoError::new(e, IoErrorKind::ReadingArchive)),
+        };
+        let name = decoder
+            .header()
+            .filename()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let result = self.read_member(&mut decoder, Some(data.len() as u64));
+        self.scan_member(path, name, result, depth);
+    }
+
+    fn unpack_zip(&mut self, path: Vec<String>, data: &[u8], depth: usize) {
+        let mut zip = match zip::ZipArchive::new(Cursor::new(data)) {
+            Ok(zip) => zip,
+            Err(e) => return self.error(path, IoError::new(e.into(), IoErrorKind::ReadingArchive)),
+        };
+        for i in 0..zip.len() {
+            if self.stopped {
+                return;
+            }
+            // The name is read from the raw member, to report the members that can not be
+            // decompressed, e.g. because they are encrypted.
+            let name = match zip.by_index_raw(i) {
+                Ok(file) if !file.is_file() => continue,
+                Ok(file) => file.name().to_owned(),
+                Err(e) => {
+                    let error = IoError::new(e.into(), IoErrorKind::ReadingArchive);
+                    self.scan_member(path.clone(), format!("#{i}"), Err(error.into()), depth);
+                    continue;
+                }
+            };
+            let result = match zip.by_index(i) {
+                Ok(mut file) => {
+                    let compressed_size = file.compressed_size();
+                    self.read_member(&mut file, Some(compressed_size))
+                }
+                Err(e) => Err(IoError::new(e.into(), IoErrorKind::ReadingArchive).into()),
+            };
+            self.scan_member(path.clone(), name, result, depth);
+        }
+    }
+
+    fn unpack_tar(&mut self, path: Vec<String>, data: &[u8], depth: usize) {
+        let mut tar = tar::Archive::new(data);
+        let entries = match tar.entries() {
+            Ok(entries) => entries,
+            Err(e) => return self.error(path, IoError::new(e, IoErrorKind::ReadingArchive)),
+        };
+        for entry in entries {
+            if self.stopped {
+                return;
+            }
+            let mut entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => return self.error(path, IoError::new(e, IoErrorKind::ReadingArchive)),
+            };
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            let result = self.read_member(&mut entry, None);
+            self.scan_member(path.clone(), name, result, depth);
+        }
+    }
+
+    /// Scan a member read from the archive at `path`.
+    fn scan_member(
+        &mut self,
+        mut path: Vec<String>,
+        name: String,
+        data: Result<Vec<u8>, Error>,
+        depth: usize,
+    ) {
+        path.push(name);
+        match data {
+            Ok(data) => self.scan(path, &data, depth),
+            Err(e) => self.error(path, e),
+        }
+    }
+
+    /// Read a member, within the limits.
+    ///
+    /// `compressed_size` is the size of the member in the archive, if it is compressed.
+    fn read_member(
+        &mut self,
+        reader: &mut dyn Read,
+        compressed_size: Option<u64>,
+    ) -> Result<Vec<u8>, Error> {
+        let max_member_size = self.archive.max_member_size;
+        let max_ratio_size = compressed_size.map(|size| {
+            size.saturating_mul(self.archive.max_ratio)
+                .max(RATIO_MIN_SIZE)
+        });
+        let max_total_size = self.archive.max_total_size - self.total_size;
+        let limit = max_member_size
+            .min(max_ratio_size.unwrap_or(u64::MAX))
+            .min(max_total_size);
+
+        let mut data = Vec::new();
+        reader
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut data)
+            .map_err(|e| IoError::new(e, IoErrorKind::ReadingArchive))?;
+        let size = data.len() as u64;
+        if size > max_member_size {
+            return Err(ArchiveError::MemberTooLarge(max_member_size).into());
+        }
+        if max_ratio_size.map_or(false, |max_size| size > max_size) {
+            return Err(ArchiveError::RatioExceeded(self.archive.max_ratio).into());
+        }
+        if size > max_total_size {
+            self.stopped = true;
+            return Err(ArchiveError::TotalSizeExceeded(self.archive.max_total_size).into());
+        }
+        self.total_size += size;
+        Ok(data)
+    }
+
+    fn error(&mut self, path: Vec<String>, error: impl Into<Error>) {
+        self.members.push(ScannedMember {
+            path,
+            result: Err(error.into()),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(Format::detect(b"\x1f\x8b\x08\x00"), Some(Format::Gzip));
+        assert_eq!(Format::detect(b"PK\x03\x04\x14\x00"), Some(Format::Zip));
+        assert_eq!(Format::detect(b"PK\x05\x06"), Some(Format::Zip));
+        let mut tar = vec![0; 512];
+        tar[257..263].copy_from_slice(b"ustar\0");
+        assert_eq!(Format::detect(&tar), Some(Format::Tar));
+        assert_eq!(Format::detect(b"PK"), None);
+        assert_eq!(Format::detect(b""), None);
+    }
+}
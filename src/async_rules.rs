@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+#[cfg(windows)]
+use std::os::windows::io::AsRawHandle as AsRawFd;
+
+use bytes::Bytes;
+use tokio::sync::Semaphore;
+
+use crate::cancellation::CancellationToken;
+use crate::errors::*;
+use crate::options::ScanOptions;
+use crate::outcome::ScanOutcome;
+use crate::rules::{OwnedRule, Rules};
+
+/// Async wrapper around compiled [`Rules`].
+///
+/// Each scan runs on Tokio's blocking thread pool, so it does not block the executor.
+/// At most `max_concurrent_scans` scans run at the same time, the other ones wait for
+/// their turn.
+///
+/// Dropping a scan future cancels the scan with a [`CancellationToken`], and its results
+/// are discarded. A scan that has not started yet is skipped. A running scan keeps running
+/// on its blocking thread, and holds its place among the `max_concurrent_scans`, until
+/// libyara calls back: usually only when the whole data is scanned.
+///
+/// Requires the `tokio` feature.
+///
+/// # Example
+///
+/// ```
+/// # use std::time::Duration;
+/// # use yara::{AsyncRules, Compiler};
+/// let rules = Compiler::new()?
+///     .add_rules_str("rule contains_rust { strings: $rust = \"rust\" nocase condition: $rust }")?
+///     .compile_rules()?;
+/// let rules = AsyncRules::new(rules, 4);
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let results = runtime.block_on(rules.scan_mem("I love Rust!".into(), Duration::from_secs(5)))?;
+/// assert_eq!("contains_rust", results[0].identifier);
+/// # Ok::<(), yara::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct AsyncRules {
+    rules: Arc<Rules>,
+    permits: Arc<Semaphore>,
+}
+
+impl AsyncRules {
+    /// Wrap the rules, allowing at most `max_concurrent_scans` scans at the same time.
+    pub fn new(rules: impl Into<Arc<Rules>>, max_concurrent_scans: usize) -> Self {
+        AsyncRules {
+            rules: rules.into(),
+            permits: Arc::new(Semaphore::new(max_concurrent_scans)),
+        }
+    }
+
+    /// The wrapped rules.
+    pub fn rules(&self) -> &Arc<Rules> {
+        &self.rules
+    }
+
+    /// Scan memory.
+    ///
+    /// Returns a `Vec` of matching rules.
+    ///
+    /// * `mem` - Bytes to scan.
+    /// * `timeout` - the timeout, rounded up to the second. Zero means no timeout.
+    pub async fn scan_mem(&self, mem: Bytes, timeout: Duration) -> Result<Vec<OwnedRule>, Error> {
+        let timeout = timeout_secs(timeout);
+        self.scan(move |rules, options| rules.scan_mem_owned(&mem, timeout, options))
+            .await
+    }
+
+    /// Scan a file.
+    ///
+    /// Returns a `Vec` of matching rules.
+    ///
+    /// * `timeout` - the timeout, rounded up to the second. Zero means no timeout.
+    pub async fn scan_file(
+        &self,
+        path: PathBuf,
+        timeout: Duration,
+    ) -> Result<Vec<OwnedRule>, Error> {
+        let timeout = timeout_secs(timeout);
+        self.scan(move |rules, options| rules.scan_file_owned(path, timeout, options))
+            .await
+    }
+
+    /// Scan a file descriptor (a file handle on Windows).
+    ///
+    /// The descriptor is moved to the blocking thread and dropped when the scan is over.
+    ///
+    /// Returns a `Vec` of matching rules.
+    ///
+    /// * `timeout` - the timeout, rounded up to the second. Zero means no timeout.
+    pub async fn scan_fd<F>(&self, fd: F, timeout: Duration) -> Result<Vec<OwnedRule>, Error>
+    where
+        F: AsRawFd + Send + 'static,
+    {
+        let timeout = timeout_secs(timeout);
+        self.scan(move |rules, options| rules.scan_fd_owned(&fd, timeout, options))
+            .await
+    }
+
+    async fn scan<S>(&self, scan: S) -> Result<Vec<OwnedRule>, Error>
+    where
+        S: FnOnce(&Rules, &ScanOptions) -> ScanOutcome + Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        let rules = self.rules.clone();
+        let token = CancellationToken::new();
+        let _cancel_on_drop = CancelOnDrop(token.clone());
+        let options = ScanOptions::new().cancellation_token(token);
+
+        let task = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            scan(&rules, &options).into_result()
+        });
+
+        match task.await {
+            Ok(result) => result,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+}
+
+/// Cancel the scan when the scan future is dropped.
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Convert a timeout to Yara's whole seconds, rounding up.
+fn timeout_secs(timeout: Duration) -> i32 {
+    let secs = timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0);
+    i32::try_from(secs).unwrap_or(i32::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn timeout_rounds_up() {
+        assert_eq!(0, timeout_secs(Duration::ZERO));
+        assert_eq!(1, timeout_secs(Duration::from_millis(1)));
+        assert_eq!(2, timeout_secs(Duration::from_secs(2)));
+        assert_eq!(3, timeout_secs(Duration::from_millis(2001)));
+        assert_eq!(i32::MAX, timeout_secs(Duration::MAX));
+    }
+}
@@ -0,0 +1,337 @@
+//! Command line parsing, following the options of the C `yara` tool.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: yara-rs [OPTION]... [NAMESPACE:]RULES_FILE... FILE | DIR | PID
+
+  -t,  --tag=TAG                    print only rules tagged as TAG
+  -i,  --identifier=IDENTIFIER      print only rules named IDENTIFIER
+  -n,  --negate                     print only not satisfied rules (negate)
+  -c,  --count                      print only number of matches
+  -d,  --define=VAR=VALUE           define external variable
+  -s,  --print-strings              print matching strings
+  -m,  --print-meta                 print metadata
+  -D,  --print-module-data          print module data
+  -C,  --compiled-rules             load compiled rules
+  -r,  --recursive                  recursively search directories
+  -N,  --no-follow-symlinks         do not follow symlinks when scanning
+  -p,  --threads=NUMBER             use the specified NUMBER of threads to scan a directory
+  -a,  --timeout=SECONDS            abort scanning after the given number of SECONDS
+       --json                       print the results as JSON, one object per scanned file
+  -h,  --help                       show this help and exit
+  -v,  --version                    show version information
+";
+
+/// Default timeout of the C tool.
+const DEFAULT_TIMEOUT: i32 = 1_000_000;
+
+/// Short name, long name, and whether the option takes a value.
+const OPTIONS: &[(Option<char>, &str, bool)] = &[
+    (Some('t'), "tag", true),
+    (Some('i'), "identifier", true),
+    (Some('n'), "negate", false),
+    (Some('c'), "count", false),
+    (Some('d'), "define", true),
+    (Some('s'), "print-strings", false),
+    (Some('m'), "print-meta", false),
+    (Some('D'), "print-module-data", false),
+    (Some('C'), "compiled-rules", false),
+    (Some('r'), "recursive", false),
+    (Some('N'), "no-follow-symlinks", false),
+    (Some('p'), "threads", true),
+    (Some('a'), "timeout", true),
+    (None, "json", false),
+    (Some('h'), "help", false),
+    (Some('v'), "version", false),
+];
+
+pub enum Command {
+    Scan(Args),
+    Help,
+    Version,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    /// Rules files, with their optional namespace.
+    pub rules: Vec<(Option<String>, PathBuf)>,
+    pub target: OsString,
+    pub tags: Vec<String>,
+    pub identifiers: Vec<String>,
+    pub negate: bool,
+    pub count: bool,
+    pub defines: Vec<(String, Define)>,
+    pub print_strings: bool,
+    pub print_meta: bool,
+    pub print_module_data: bool,
+    pub compiled_rules: bool,
+    pub recursive: bool,
+    pub follow_symlinks: bool,
+    pub threads: usize,
+    pub timeout: i32,
+    pub json: bool,
+}
+
+/// Value of an external variable given with `-d`.
+#[derive(Debug, PartialEq)]
+pub enum Define {
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Define {
+    /// Parse the value like the C tool: booleans, then integers, then floats.
+    fn parse(value: &str) -> Self {
+        match value {
+            "true" => Define::Boolean(true),
+            "false" => Define::Boolean(false),
+            _ => {
+                if let Ok(i) = value.parse() {
+                    Define::Integer(i)
+                } else if let (true, Ok(f)) = (value.contains('.'), value.parse()) {
+                    Define::Float(f)
+                } else {
+                    Define::String(value.to_string())
+                }
+            }
+        }
+    }
+}
+
+impl Args {
+    fn new() -> Self {
+        Args {
+            rules: Vec::new(),
+            target: OsString::new(),
+            tags: Vec::new(),
+            identifiers: Vec::new(),
+            negate: false,
+            count: false,
+            defines: Vec::new(),
+            print_strings: false,
+            print_meta: false,
+            print_module_data: false,
+            compiled_rules: false,
+            recursive: false,
+            follow_symlinks: true,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            timeout: DEFAULT_TIMEOUT,
+            json: false,
+        }
+    }
+
+    /// Returns true if the `option` stops the parsing (help or version).
+    fn set(&mut self, option: &str, value: Option<String>) -> Result<bool, String> {
+        let value = value.unwrap_or_default();
+        match option {
+            "tag" => self.tags.push(value),
+            "identifier" => self.identifiers.push(value),
+            "negate" => self.negate = true,
+            "count" => self.count = true,
+            "define" => {
+                let (identifier, value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid define: {value}"))?;
+                self.defines
+                    .push((identifier.to_string(), Define::parse(value)));
+            }
+            "print-strings" => self.print_strings = true,
+            "print-meta" => self.print_meta = true,
+            "print-module-data" => self.print_module_data = true,
+            "compiled-rules" => self.compiled_rules = true,
+            "recursive" => self.recursive = true,
+            "no-follow-symlinks" => self.follow_symlinks = false,
+            "threads" => {
+                self.threads = value
+                    .parse()
+                    .ok()
+                    .filter(|&threads| threads > 0)
+                    .ok_or_else(|| format!("invalid number of threads: {value}"))?;
+            }
+            "timeout" => {
+                self.timeout = value
+                    .parse()
+                    .map_err(|_| format!("invalid timeout: {value}"))?;
+            }
+            "json" => self.json = true,
+            _ => return Ok(true),
+        }
+        Ok(false)
+    }
+}
+
+pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Command, String> {
+    let mut parsed = Args::new();
+    let mut positionals = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let text = match arg.to_str() {
+            Some(text) => text,
+            None => {
+                positionals.push(arg);
+                continue;
+            }
+        };
+
+        if text == "--" {
+            positionals.extend(args.by_ref());
+        } else if let Some(long) = text.strip_prefix("--") {
+            let (name, inline_value) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let &(_, name, takes_value) = OPTIONS
+                .iter()
+                .find(|(_, long, _)| *long == name)
+                .ok_or_else(|| format!("unrecognized option '--{name}'"))?;
+            let value = match (takes_value, inline_value) {
+                (true, Some(value)) => Some(value),
+                (true, None) => Some(next_value(&mut args, name)?),
+                (false, Some(_)) => return Err(format!("option '--{name}' takes no value")),
+                (false, None) => None,
+            };
+            if parsed.set(name, value)? {
+                return Ok(stop_command(name));
+            }
+        } else if text.len() > 1 && text.starts_with('-') {
+            // Cluster of short options, the last one can take a value: -rsp4
+            for (i, c) in text.char_indices().skip(1) {
+                let &(_, name, takes_value) = OPTIONS
+                    .iter()
+                    .find(|(short, _, _)| *short == Some(c))
+                    .ok_or_else(|| format!("invalid option -- '{c}'"))?;
+                let rest = &text[i + c.len_utf8()..];
+                let value = match (takes_value, rest.is_empty()) {
+                    (true, false) => Some(rest.to_string()),
+                    (true, true) => Some(next_value(&mut args, name)?),
+                    (false, _) => None,
+                };
+                if parsed.set(name, value)? {
+                    return Ok(stop_command(name));
+                }
+                if takes_value {
+                    break;
+                }
+            }
+        } else {
+            positionals.push(arg);
+        }
+    }
+
+    parsed.target = positionals
+        .pop()
+        .ok_or_else(|| "wrong number of arguments".to_string())?;
+    if positionals.is_empty() || (parsed.compiled_rules && positionals.len() > 1) {
+        return Err("wrong number of arguments".to_string());
+    }
+    parsed.rules = positionals.into_iter().map(split_namespace).collect();
+
+    Ok(Command::Scan(parsed))
+}
+
+fn stop_command(option: &str) -> Command {
+    if option == "help" {
+        Command::Help
+    } else {
+        Command::Version
+    }
+}
+
+fn next_value(args: &mut impl Iterator<Item = OsString>, option: &str) -> Result<String, String> {
+    args.next()
+        .and_then(|value| value.into_string().ok())
+        .ok_or_else(|| format!("option '{option}' requires a value"))
+}
+
+/// Split `NAMESPACE:RULES_FILE`, unless the whole argument is an existing file.
+fn split_namespace(arg: OsString) -> (Option<String>, PathBuf) {
+    let path = PathBuf::from(&arg);
+    if path.exists() {
+        return (None, path);
+    }
+    match arg.to_str().and_then(|arg| arg.split_once(':')) {
+        Some((namespace, file)) => (Some(namespace.to_string()), PathBuf::from(file)),
+        None => (None, path),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Args, String> {
+        match parse(args.iter().map(OsString::from))? {
+            Command::Scan(args) => Ok(args),
+            _ => Err("not a scan".to_string()),
+        }
+    }
+
+    #[test]
+    fn parse_options() {
+        let args = parse_args(&[
+            "-rsm",
+            "-p4",
+            "--timeout",
+            "10",
+            "-t",
+            "a",
+            "--tag=b",
+            "-d",
+            "int=-3",
+            "--define=float=1.5",
+            "-dbool=true",
+            "-dstr=a=b",
+            "--json",
+            "ns:missing_rules.yar",
+            "other.yar",
+            "dir",
+        ])
+        .unwrap();
+
+        assert!(args.recursive && args.print_strings && args.print_meta && args.json);
+        assert!(!args.negate && !args.count && !args.compiled_rules);
+        assert!(args.follow_symlinks);
+        assert_eq!(args.threads, 4);
+        assert_eq!(args.timeout, 10);
+        assert_eq!(args.tags, ["a", "b"]);
+        assert_eq!(
+            args.defines,
+            [
+                ("int".to_string(), Define::Integer(-3)),
+                ("float".to_string(), Define::Float(1.5)),
+                ("bool".to_string(), Define::Boolean(true)),
+                ("str".to_string(), Define::String("a=b".to_string())),
+            ]
+        );
+        assert_eq!(
+            args.rules,
+            [
+                (Some("ns".to_string()), PathBuf::from("missing_rules.yar")),
+                (None, PathBuf::from("other.yar")),
+            ]
+        );
+        assert_eq!(args.target, "dir");
+
+        let args = parse_args(&["-rN", "rules.yar", "dir"]).unwrap();
+        assert!(args.recursive && !args.follow_symlinks);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_args(&["rules.yar"]).is_err());
+        assert!(parse_args(&["-C", "a.yarc", "b.yarc", "file"]).is_err());
+        assert!(parse_args(&["-x", "rules.yar", "file"]).is_err());
+        assert!(parse_args(&["-p", "0", "rules.yar", "file"]).is_err());
+        assert!(parse_args(&["-d", "novalue", "rules.yar", "file"]).is_err());
+        assert!(parse_args(&["rules.yar", "file", "-a"]).is_err());
+        assert!(matches!(
+            parse(["-h"].iter().map(OsString::from)),
+            Ok(Command::Help)
+        ));
+    }
+}
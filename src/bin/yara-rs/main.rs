@@ -0,0 +1,199 @@
+//! `yara-rs`, a command line tool compatible with the `yara` tool of libyara.
+//!
+//! Built with the `cli` feature.
+
+mod args;
+mod output;
+
+use std::error::Error as StdError;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use std::process;
+
+use serde_json::{Map, Value};
+use yara::walk::{SymlinkPolicy, Walker};
+use yara::{
+    CallbackMsg, CallbackReturn, Compiler, Error, IoError, IoErrorKind, OwnedRule, Rules, ScanFlags,
+};
+
+use crate::args::{Args, Command, Define, USAGE};
+use crate::output::HexStrings;
+
+/// What was reported during the scan of a file or process.
+#[derive(Default)]
+pub struct Report {
+    /// Matching rules, or not matching rules with `-n`.
+    pub rules: Vec<OwnedRule>,
+    /// Module data printed with `-D`.
+    pub modules: Vec<String>,
+    /// Module data printed with `-D --json`, by module name.
+    pub modules_json: Map<String, Value>,
+}
+
+impl Report {
+    fn collect(&mut self, message: CallbackMsg, module_data: ModuleData) -> CallbackReturn {
+        match (message, module_data) {
+            (CallbackMsg::RuleMatching(rule) | CallbackMsg::RuleNotMatching(rule), _) => {
+                self.rules.push(OwnedRule::from(rule))
+            }
+            (CallbackMsg::ModuleImported(object), ModuleData::Text) => {
+                self.modules.push(output::module_data(&object))
+            }
+            (CallbackMsg::ModuleImported(object), ModuleData::Json) => {
+                let name = String::from_utf8_lossy(object.identifier().unwrap_or_default());
+                let value = serde_json::to_value(&object).unwrap_or(Value::Null);
+                self.modules_json.insert(name.into_owned(), value);
+            }
+            _ => {}
+        }
+        CallbackReturn::Continue
+    }
+}
+
+/// How to report the module data.
+#[derive(Clone, Copy)]
+enum ModuleData {
+    Ignore,
+    Text,
+    Json,
+}
+
+impl ModuleData {
+    fn new(args: &Args) -> Self {
+        match (args.print_module_data, args.json) {
+            (false, _) => ModuleData::Ignore,
+            (true, false) => ModuleData::Text,
+            (true, true) => ModuleData::Json,
+        }
+    }
+}
+
+fn main() {
+    let args = match args::parse(std::env::args_os().skip(1)) {
+        Ok(Command::Scan(args)) => args,
+        Ok(Command::Help) => {
+            print!("{USAGE}");
+            return;
+        }
+        Ok(Command::Version) => {
+            println!("{}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(error) => {
+            eprintln!("yara-rs: {error}");
+            eprint!("{USAGE}");
+            process::exit(1);
+        }
+    };
+
+    match run(args) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(error) => {
+            eprintln!("error: {error}");
+            process::exit(1);
+        }
+    }
+}
+
+/// Returns false if a scan failed.
+fn run(args: Args) -> Result<bool, Box<dyn StdError>> {
+    let rules = load_rules(&args)?;
+    let hex_strings = if args.print_strings {
+        HexStrings::new(&rules)
+    } else {
+        HexStrings::default()
+    };
+    let flags = if args.negate {
+        ScanFlags::REPORT_RULES_NOT_MATCHING
+    } else {
+        ScanFlags::REPORT_RULES_MATCHING
+    };
+    let target = PathBuf::from(&args.target);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut success = true;
+    let module_data = ModuleData::new(&args);
+
+    if target.exists() {
+        let walk = Walker::new(rules, [target])
+            .workers(args.threads)
+            .recursive(args.recursive)
+            .symlinks(if args.follow_symlinks {
+                SymlinkPolicy::Follow
+            } else {
+                SymlinkPolicy::Skip
+            })
+            .timeout(args.timeout)
+            .flags(flags)
+            .walk_with(move |scanner, path| {
+                let mut report = Report::default();
+                scanner.scan_file_callback(path, |message| report.collect(message, module_data))?;
+                Ok(report)
+            });
+        for file in walk {
+            success &= file.result.is_ok();
+            let path = file.path.to_string_lossy();
+            output::print(&mut out, &args, &hex_strings, &path, &file.result)?;
+        }
+    } else if let Some(pid) = pid(&args.target) {
+        let mut scanner = rules.scanner()?;
+        scanner.set_timeout(args.timeout);
+        scanner.set_flags(flags);
+        let mut report = Report::default();
+        let result = scanner
+            .scan_process_callback(pid, |message| report.collect(message, module_data))
+            .map(|()| report)
+            .map_err(Error::from);
+        success = result.is_ok();
+        output::print(&mut out, &args, &hex_strings, &pid.to_string(), &result)?;
+    } else {
+        return Err(format!("could not open file: {}", target.display()).into());
+    }
+
+    Ok(success)
+}
+
+fn pid(target: &OsString) -> Option<u32> {
+    target.to_str().and_then(|target| target.parse().ok())
+}
+
+fn load_rules(args: &Args) -> Result<Rules, Error> {
+    if args.compiled_rules {
+        let (_, path) = &args.rules[0];
+        let file = File::open(path).map_err(|e| IoError::new(e, IoErrorKind::OpenRulesFile))?;
+        let mut rules = Rules::load_from_stream(BufReader::new(file))?;
+        for (identifier, value) in &args.defines {
+            match value {
+                Define::Boolean(b) => rules.define_variable(identifier, *b)?,
+                Define::Integer(i) => rules.define_variable(identifier, *i)?,
+                Define::Float(f) => rules.define_variable(identifier, *f)?,
+                Define::String(s) => rules.define_variable(identifier, s.as_str())?,
+            }
+        }
+        return Ok(rules);
+    }
+
+    let mut compiler = Compiler::new()?;
+    for (identifier, value) in &args.defines {
+        match value {
+            Define::Boolean(b) => compiler.define_variable(identifier, *b)?,
+            Define::Integer(i) => compiler.define_variable(identifier, *i)?,
+            Define::Float(f) => compiler.define_variable(identifier, *f)?,
+            Define::String(s) => compiler.define_variable(identifier, s.as_str())?,
+        }
+    }
+    for (namespace, path) in &args.rules {
+        compiler = match namespace {
+            Some(namespace) => compiler.add_rules_file_with_namespace(path, namespace)?,
+            None => compiler.add_rules_file(path)?,
+        };
+    }
+    let (rules, warnings) = compiler.compile_rules_with_warnings()?;
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+    Ok(rules)
+}
@@ -0,0 +1,310 @@
+//! Printing of the scan results, in the format of the C `yara` tool or as JSON.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use serde_json::{json, Map, Value};
+use yara::{Error, OwnedMetadataValue, OwnedRule, RuleStringKind, Rules, YrObject, YrObjectValue};
+
+use crate::args::Args;
+use crate::Report;
+
+/// Maximum number of bytes of a hex string printed by `-s`, like the C tool.
+const MAX_HEX_BYTES: usize = 64;
+
+/// The hex strings of the rules, whose matches `-s` prints as bytes like the C tool.
+#[derive(Default)]
+pub struct HexStrings(HashSet<(String, String, String)>);
+
+impl HexStrings {
+    pub fn new(rules: &Rules) -> Self {
+        HexStrings(
+            rules
+                .get_rules()
+                .into_iter()
+                .flat_map(|rule| {
+                    rule.strings
+                        .into_iter()
+                        .filter(|string| string.kind == RuleStringKind::Hex)
+                        .map(move |string| {
+                            (
+                                rule.namespace.to_string(),
+                                rule.identifier.to_string(),
+                                string.identifier.to_string(),
+                            )
+                        })
+                })
+                .collect(),
+        )
+    }
+
+    fn contains(&self, rule: &OwnedRule, string: &str) -> bool {
+        self.0.contains(&(
+            rule.namespace.clone(),
+            rule.identifier.clone(),
+            string.to_string(),
+        ))
+    }
+}
+
+/// Print the result of the scan of `target`.
+pub fn print(
+    out: &mut impl Write,
+    args: &Args,
+    hex_strings: &HexStrings,
+    target: &str,
+    result: &Result<Report, Error>,
+) -> io::Result<()> {
+    match (result, args.json) {
+        (Ok(report), false) => print_text(out, args, hex_strings, target, report),
+        (Ok(report), true) => writeln!(out, "{}", report_json(args, target, report)),
+        (Err(error), false) => {
+            eprintln!("error scanning {target}: {error}");
+            Ok(())
+        }
+        (Err(error), true) => {
+            let error = json!({ "path": target, "error": error.to_string() });
+            writeln!(out, "{error}")
+        }
+    }
+}
+
+fn print_text(
+    out: &mut impl Write,
+    args: &Args,
+    hex_strings: &HexStrings,
+    target: &str,
+    report: &Report,
+) -> io::Result<()> {
+    for module in &report.modules {
+        writeln!(out, "{module}")?;
+    }
+
+    if args.count {
+        let count = report.rules.iter().filter(|r| is_shown(args, r)).count();
+        return writeln!(out, "{target}: {count}");
+    }
+
+    for rule in report.rules.iter().filter(|r| is_shown(args, r)) {
+        write!(out, "{} ", rule.identifier)?;
+        if args.print_meta {
+            let metadatas: Vec<String> = rule
+                .metadatas
+                .iter()
+                .map(|meta| match &meta.value {
+                    OwnedMetadataValue::Integer(i) => format!("{}={}", meta.identifier, i),
+                    OwnedMetadataValue::Boolean(b) => format!("{}={}", meta.identifier, b),
+                    OwnedMetadataValue::String(s) => {
+                        format!("{}=\"{}\"", meta.identifier, escape(s.as_bytes()))
+                    }
+                })
+                .collect();
+            write!(out, "[{}] ", metadatas.join(","))?;
+        }
+        writeln!(out, "{target}")?;
+
+        if args.print_strings {
+            for string in &rule.strings {
+                let is_hex = hex_strings.contains(rule, &string.identifier);
+                for m in &string.matches {
+                    let data = if is_hex {
+                        hex(&m.data)
+                    } else {
+                        escape(&m.data)
+                    };
+                    writeln!(
+                        out,
+                        "0x{:x}:{}: {}",
+                        m.base + m.offset,
+                        string.identifier,
+                        data
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn report_json(args: &Args, target: &str, report: &Report) -> Value {
+    let rules = report.rules.iter().filter(|r| is_shown(args, r));
+    if args.count {
+        return json!({ "path": target, "count": rules.count() });
+    }
+
+    let matches: Vec<Value> = rules
+        .map(|rule| {
+            let metadatas: Map<String, Value> = rule
+                .metadatas
+                .iter()
+                .map(|meta| {
+                    let value = match &meta.value {
+                        OwnedMetadataValue::Integer(i) => json!(i),
+                        OwnedMetadataValue::Boolean(b) => json!(b),
+                        OwnedMetadataValue::String(s) => json!(s),
+                    };
+                    (meta.identifier.clone(), value)
+                })
+                .collect();
+            let mut value = json!({
+                "identifier": rule.identifier,
+                "namespace": rule.namespace,
+                "tags": rule.tags,
+                "meta": metadatas,
+            });
+            if args.print_strings {
+                let strings: Vec<Value> = rule
+                    .strings
+                    .iter()
+                    .flat_map(|string| {
+                        string.matches.iter().map(move |m| {
+                            json!({
+                                "identifier": string.identifier,
+                                "offset": m.base + m.offset,
+                                "data": json_data(&m.data),
+                            })
+                        })
+                    })
+                    .collect();
+                value["strings"] = Value::Array(strings);
+            }
+            value
+        })
+        .collect();
+
+    let mut value = json!({ "path": target, "matches": matches });
+    if args.print_module_data {
+        value["modules"] = Value::Object(report.modules_json.clone());
+    }
+    value
+}
+
+/// Apply the `-t` and `-i` filters.
+fn is_shown(args: &Args, rule: &OwnedRule) -> bool {
+    (args.tags.is_empty() || rule.tags.iter().any(|tag| args.tags.contains(tag)))
+        && (args.identifiers.is_empty() || args.identifiers.contains(&rule.identifier))
+}
+
+/// Escape `data` like the C tool prints the matching strings and the metadata.
+///
+/// `"`, `'` and `\` are escaped with a backslash, and the bytes out of the printable
+/// ASCII range are written as `\xNN`.
+fn escape(data: &[u8]) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for &c in data {
+        match c {
+            b'"' | b'\'' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(c as char);
+            }
+            32..=126 => escaped.push(c as char),
+            _ => write!(escaped, "\\x{c:02X}").unwrap(),
+        }
+    }
+    escaped
+}
+
+/// The data of a match in JSON: a string if it is valid UTF-8, an array of bytes
+/// otherwise.
+fn json_data(data: &[u8]) -> Value {
+    match std::str::from_utf8(data) {
+        Ok(s) => json!(s),
+        Err(_) => json!(data),
+    }
+}
+
+/// Format the bytes of a hex string, like the C tool.
+fn hex(data: &[u8]) -> String {
+    let mut hex: Vec<String> = data
+        .iter()
+        .take(MAX_HEX_BYTES)
+        .map(|b| format!("{b:02X}"))
+        .collect();
+    if data.len() > MAX_HEX_BYTES {
+        hex.push("...".to_string());
+    }
+    hex.join(" ")
+}
+
+/// Format the data of a module like `yara -D`.
+pub fn module_data(module: &YrObject) -> String {
+    let mut data = String::new();
+    write_object(&mut data, module, 0, true);
+    data
+}
+
+fn write_object(out: &mut String, object: &YrObject, indent: usize, print_identifier: bool) {
+    let indent_spaces = "\t".repeat(indent);
+    let value = object.value();
+    if print_identifier && !matches!(value, YrObjectValue::Function) {
+        let identifier = object.identifier().unwrap_or_default();
+        write!(
+            out,
+            "{}{}",
+            indent_spaces,
+            String::from_utf8_lossy(identifier)
+        )
+        .unwrap();
+    }
+
+    match value {
+        YrObjectValue::Integer(i) => write!(out, " = {i}").unwrap(),
+        YrObjectValue::Float(f) => write!(out, " = {f:.6}").unwrap(),
+        YrObjectValue::String(s) => write!(out, " = \"{}\"", escape(s)).unwrap(),
+        YrObjectValue::Undefined => out.push_str(" = YR_UNDEFINED"),
+        YrObjectValue::Structure(members) => {
+            for member in &members {
+                if !matches!(member.value(), YrObjectValue::Function) {
+                    out.push('\n');
+                    write_object(out, member, indent + 1, true);
+                }
+            }
+        }
+        YrObjectValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if let Some(item) = item {
+                    write!(out, "\n{indent_spaces}\t[{i}]").unwrap();
+                    write_object(out, item, indent + 1, false);
+                }
+            }
+        }
+        YrObjectValue::Dictionary(_) => {
+            for (key, item) in object.iter_dict().into_iter().flatten() {
+                write!(out, "\n{}\t{}", indent_spaces, String::from_utf8_lossy(key)).unwrap();
+                write_object(out, &item, indent + 1, false);
+            }
+        }
+        YrObjectValue::Function => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, hex, json_data};
+    use serde_json::json;
+
+    #[test]
+    fn escape_data() {
+        assert_eq!(escape(b"Rust"), "Rust");
+        assert_eq!(escape(b"\"a\" 'b' \\"), "\\\"a\\\" \\'b\\' \\\\");
+        assert_eq!(escape(b"a\tb\r\n"), "a\\x09b\\x0D\\x0A");
+        assert_eq!(escape(b"\x00\x7f\xff"), "\\x00\\x7F\\xFF");
+    }
+
+    #[test]
+    fn json_match_data() {
+        assert_eq!(json_data(b"a\"b"), json!("a\"b"));
+        assert_eq!(json_data(b"\xff\x00"), json!([255, 0]));
+    }
+
+    #[test]
+    fn hex_data() {
+        assert_eq!(hex(b"Rust\n"), "52 75 73 74 0A");
+        let long = hex(&[0xab; 65]);
+        assert!(long.starts_with("AB AB "));
+        assert!(long.ends_with(" AB ..."));
+        assert_eq!(long.matches("AB").count(), 64);
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::internals::MemoryBlock;
+use crate::{MemoryBlockIterator, MemoryBlockIteratorSized};
+
+/// A handle to cancel scans from another thread.
+///
+/// The token is passed to a scan with
+/// [`ScanOptions::cancellation_token`](crate::ScanOptions::cancellation_token). Cancelling
+/// it only stops the scans it was passed to, so create one token per scan, or per group of
+/// scans to cancel together.
+///
+/// A cancelled scan stops the next time libyara calls back, or before the next memory
+/// block of a `scan_mem_blocks*` scan. Note that when scanning a single buffer, libyara
+/// only calls back once the data is scanned, or when a string has too many matches.
+/// The [`ScanOutcome`](crate::ScanOutcome) of the scan then has the rules matched before
+/// that, and [`Error::Cancelled`](crate::Error::Cancelled). A scan that was already over
+/// when the token was cancelled is not affected.
+///
+/// Once cancelled, the token stays cancelled and the following scans using it are
+/// cancelled immediately.
+///
+/// # Example
+///
+/// ```
+/// # use yara::{CancellationToken, Compiler, Error, ScanOptions};
+/// let rules = Compiler::new()?
+///     .add_rules_str("rule contains_rust { strings: $rust = \"rust\" condition: $rust }")?
+///     .compile_rules()?;
+/// let mut scanner = rules.scanner()?;
+/// let token = CancellationToken::new();
+/// let cancel = token.clone();
+/// std::thread::spawn(move || cancel.cancel()).join().unwrap();
+/// let outcome = scanner.scan_mem_owned(b"rust", &ScanOptions::new().cancellation_token(token));
+/// assert!(matches!(outcome.error, Some(Error::Cancelled)));
+/// # Ok::<(), yara::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token, not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the ongoing and following scans using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// A memory block iterator stopping before the next block once its token is cancelled.
+pub(crate) struct CancellableIterator<I> {
+    inner: I,
+    token: Option<CancellationToken>,
+    stopped: bool,
+}
+
+impl<I> CancellableIterator<I> {
+    pub(crate) fn new(inner: I, token: Option<&CancellationToken>) -> Self {
+        CancellableIterator {
+            inner,
+            token: token.cloned(),
+            stopped: false,
+        }
+    }
+
+    /// Whether the iteration was stopped because of the token.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    fn check(&mut self) -> bool {
+        self.stopped = self.token.as_ref().map_or(false, |t| t.is_cancelled());
+        !self.stopped
+    }
+}
+
+impl<I: MemoryBlockIterator> MemoryBlockIterator for CancellableIterator<I> {
+    fn first(&mut self) -> Option<MemoryBlock<'_>> {
+        if self.check() {
+            self.inner.first()
+        } else {
+            None
+        }
+    }
+
+    fn next(&mut self) -> Option<MemoryBlock<'_>> {
+        if self.check() {
+            self.inner.next()
+        } else {
+            None
+        }
+    }
+}
+
+impl<I: MemoryBlockIteratorSized> MemoryBlockIteratorSized for CancellableIterator<I> {
+    fn file_size(&mut self) -> u64 {
+        self.inner.file_size()
+    }
+}
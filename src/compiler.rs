@@ -26,6 +26,8 @@ pub struct Compiler {
     // The user_data used by the include callback and it's associated free function
     // Safety: It must stay alive until the end of compilation or until a new callback is set
     include_user_data: Option<Callback>,
+    // Warnings reported while adding rules.
+    warnings: Vec<CompileError>,
 }
 
 impl std::fmt::Debug for Compiler {
@@ -33,6 +35,7 @@ impl std::fmt::Debug for Compiler {
         f.debug_struct("Compiler")
             .field("inner", &self.inner)
             .field("_token", &self._token)
+            .field("warnings", &self.warnings)
             .finish()
     }
 }
@@ -46,6 +49,7 @@ impl Compiler {
             inner,
             _token: token,
             include_user_data: None,
+            warnings: Vec::new(),
         })
     }
 
@@ -63,7 +67,7 @@ impl Compiler {
         File::open(path.as_ref())
             .map_err(|e| IoError::new(e, IoErrorKind::OpenRulesFile).into())
             .and_then(|file| internals::compiler_add_file(self.inner, &file, path, None))
-            .map(|()| self)
+            .map(|warnings| self.with_warnings(warnings))
     }
 
     /// Add rule definitions from a file within a namespace.
@@ -84,7 +88,7 @@ impl Compiler {
         File::open(path.as_ref())
             .map_err(|e| IoError::new(e, IoErrorKind::OpenRulesFile).into())
             .and_then(|file| internals::compiler_add_file(self.inner, &file, path, Some(namespace)))
-            .map(|()| self)
+            .map(|warnings| self.with_warnings(warnings))
     }
 
     /// Add rule definitions from a string.
@@ -101,7 +105,8 @@ impl Compiler {
     /// # Ok::<(), yara::Error>(())
     /// ```
     pub fn add_rules_str(self, rule: &str) -> Result<Compiler, Error> {
-        internals::compiler_add_string(self.inner, rule, None).map(|()| self)
+        internals::compiler_add_string(self.inner, rule, None)
+            .map(|warnings| self.with_warnings(warnings))
     }
 
     /// Add rule definition from a string within a namespace.
@@ -122,7 +127,8 @@ impl Compiler {
         rule: &str,
         namespace: &str,
     ) -> Result<Compiler, Error> {
-        internals::compiler_add_string(self.inner, rule, Some(namespace)).map(|()| self)
+        internals::compiler_add_string(self.inner, rule, Some(namespace))
+            .map(|warnings| self.with_warnings(warnings))
     }
 
     /// Add rules definitions from a opened file.
@@ -131,7 +137,8 @@ impl Compiler {
         file: &F,
         path: P,
     ) -> Result<Compiler, Error> {
-        internals::compiler_add_file(self.inner, file, path, None).map(|()| self)
+        internals::compiler_add_file(self.inner, file, path, None)
+            .map(|warnings| self.with_warnings(warnings))
     }
 
     /// Add rules definitions from a opened file with namespace.
@@ -141,7 +148,8 @@ impl Compiler {
         path: P,
         namespace: &str,
     ) -> Result<Compiler, Error> {
-        internals::compiler_add_file(self.inner, file, path, Some(namespace)).map(|()| self)
+        internals::compiler_add_file(self.inner, file, path, Some(namespace))
+            .map(|warnings| self.with_warnings(warnings))
     }
 
     /// Compile the rules.
@@ -156,6 +164,43 @@ impl Compiler {
         internals::compiler_get_rules(self.inner).and_then(|v| unsafe { Rules::unsafe_try_from(v) })
     }
 
+    /// Compile the rules, and return them along with the warnings reported while adding them.
+    ///
+    /// Consume the compiler.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use yara::Compiler;
+    /// let compiler = Compiler::new()?
+    ///     .add_rules_str(r#"rule is_slow {
+    ///   strings:
+    ///     $re = /state:.*(on|off)/
+    ///   condition:
+    ///     $re
+    /// }"#)?;
+    /// let (rules, warnings) = compiler.compile_rules_with_warnings()?;
+    /// assert!(!warnings.is_empty());
+    /// # Ok::<(), yara::Error>(())
+    /// ```
+    pub fn compile_rules_with_warnings(mut self) -> Result<(Rules, Vec<CompileError>), YaraError> {
+        let warnings = std::mem::take(&mut self.warnings);
+        self.compile_rules().map(|rules| (rules, warnings))
+    }
+
+    /// Get the warnings reported so far while adding rules.
+    ///
+    /// Warnings do not prevent the rules from compiling, for example a string
+    /// slowing down the scan.
+    pub fn warnings(&self) -> &[CompileError] {
+        &self.warnings
+    }
+
+    fn with_warnings(mut self, warnings: Vec<CompileError>) -> Self {
+        self.warnings.extend(warnings);
+        self
+    }
+
     /// Add a variable to the compiler.
     ///
     /// Valid types are bool, i64, f64, str and cstr.
@@ -316,6 +361,13 @@ impl Drop for Compiler {
 
 /// Trait implemented by the types the compiler can use as value.
 pub trait CompilerVariableValue {
+    /// The type of the variable this value can be assigned to, if known.
+    ///
+    /// Values of an unknown type are not checked against the declaration of the variable.
+    fn variable_type(&self) -> Option<ExternalVariableType> {
+        None
+    }
+
     fn add_to_compiler(
         &self,
         compiler: *mut yara_sys::YR_COMPILER,
@@ -327,9 +379,24 @@ pub trait CompilerVariableValue {
         scanner: *mut yara_sys::YR_SCANNER,
         identifier: &str,
     ) -> Result<(), YaraError>;
+
+    /// Change the default value of the variable in compiled rules.
+    ///
+    /// Not supported by default.
+    fn assign_in_rules(
+        &self,
+        _rules: *mut yara_sys::YR_RULES,
+        _identifier: &str,
+    ) -> Result<(), YaraError> {
+        Err(YaraErrorKind::Unknown(yara_sys::ERROR_INVALID_ARGUMENT as i32).into())
+    }
 }
 
 impl CompilerVariableValue for bool {
+    fn variable_type(&self) -> Option<ExternalVariableType> {
+        Some(ExternalVariableType::Boolean)
+    }
+
     fn add_to_compiler(
         &self,
         compiler: *mut yara_sys::YR_COMPILER,
@@ -345,9 +412,21 @@ impl CompilerVariableValue for bool {
     ) -> Result<(), YaraError> {
         internals::scanner_define_boolean_variable(scanner, identifier, *self)
     }
+
+    fn assign_in_rules(
+        &self,
+        rules: *mut yara_sys::YR_RULES,
+        identifier: &str,
+    ) -> Result<(), YaraError> {
+        internals::rules_define_boolean_variable(rules, identifier, *self)
+    }
 }
 
 impl CompilerVariableValue for f64 {
+    fn variable_type(&self) -> Option<ExternalVariableType> {
+        Some(ExternalVariableType::Float)
+    }
+
     fn add_to_compiler(
         &self,
         compiler: *mut yara_sys::YR_COMPILER,
@@ -363,9 +442,21 @@ impl CompilerVariableValue for f64 {
     ) -> Result<(), YaraError> {
         internals::scanner_define_float_variable(scanner, identifier, *self)
     }
+
+    fn assign_in_rules(
+        &self,
+        rules: *mut yara_sys::YR_RULES,
+        identifier: &str,
+    ) -> Result<(), YaraError> {
+        internals::rules_define_float_variable(rules, identifier, *self)
+    }
 }
 
 impl CompilerVariableValue for i64 {
+    fn variable_type(&self) -> Option<ExternalVariableType> {
+        Some(ExternalVariableType::Integer)
+    }
+
     fn add_to_compiler(
         &self,
         compiler: *mut yara_sys::YR_COMPILER,
@@ -381,9 +472,21 @@ impl CompilerVariableValue for i64 {
     ) -> Result<(), YaraError> {
         internals::scanner_define_integer_variable(scanner, identifier, *self)
     }
+
+    fn assign_in_rules(
+        &self,
+        rules: *mut yara_sys::YR_RULES,
+        identifier: &str,
+    ) -> Result<(), YaraError> {
+        internals::rules_define_integer_variable(rules, identifier, *self)
+    }
 }
 
 impl CompilerVariableValue for &str {
+    fn variable_type(&self) -> Option<ExternalVariableType> {
+        Some(ExternalVariableType::String)
+    }
+
     fn add_to_compiler(
         &self,
         compiler: *mut yara_sys::YR_COMPILER,
@@ -399,9 +502,21 @@ impl CompilerVariableValue for &str {
     ) -> Result<(), YaraError> {
         internals::scanner_define_str_variable(scanner, identifier, self)
     }
+
+    fn assign_in_rules(
+        &self,
+        rules: *mut yara_sys::YR_RULES,
+        identifier: &str,
+    ) -> Result<(), YaraError> {
+        internals::rules_define_str_variable(rules, identifier, self)
+    }
 }
 
 impl CompilerVariableValue for &CStr {
+    fn variable_type(&self) -> Option<ExternalVariableType> {
+        Some(ExternalVariableType::String)
+    }
+
     fn add_to_compiler(
         &self,
         compiler: *mut yara_sys::YR_COMPILER,
@@ -417,4 +532,12 @@ impl CompilerVariableValue for &CStr {
     ) -> Result<(), YaraError> {
         internals::scanner_define_cstr_variable(scanner, identifier, self)
     }
+
+    fn assign_in_rules(
+        &self,
+        rules: *mut yara_sys::YR_RULES,
+        identifier: &str,
+    ) -> Result<(), YaraError> {
+        internals::rules_define_cstr_variable(rules, identifier, self)
+    }
 }
@@ -0,0 +1,321 @@
+use crate::errors::*;
+use crate::internals::{MemoryBlock, MemoryBlockIterator};
+
+use super::{dumped, invalid_dump, read_bytes};
+
+const ET_CORE: u16 = 4;
+const PT_LOAD: u32 = 1;
+/// Value of `e_phnum` when the number of program headers is in the first section header.
+const PN_XNUM: u16 = 0xffff;
+
+/// A `PT_LOAD` segment of an ELF core dump.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ElfSegment {
+    /// Virtual address of the segment in the process.
+    pub vaddr: u64,
+    /// Offset of the content of the segment in the dump.
+    pub offset: u64,
+    /// Size of the content of the segment in the dump.
+    ///
+    /// Can be smaller than the memory size, or 0, for memory that was not dumped.
+    pub file_size: u64,
+    /// Size of the segment in the process memory.
+    pub memory_size: u64,
+    /// `PF_X` (1), `PF_W` (2) and `PF_R` (4) flags of the segment.
+    pub flags: u32,
+}
+
+/// A [`MemoryBlockIterator`] over the `PT_LOAD` segments of an ELF core dump.
+///
+/// Each segment is a block whose base is its virtual address. Only the content saved in
+/// the dump is scanned: segments not dumped are skipped, and segments truncated in the dump
+/// are scanned up to its end.
+///
+/// 32 and 64-bit, little and big-endian dumps are supported.
+///
+/// # Example
+///
+/// ```no_run
+/// # use yara::Compiler;
+/// use yara::dump::ElfCoreMemoryIterator;
+///
+/// # let rules = Compiler::new()?.add_rules_str("rule r { condition: true }")?.compile_rules()?;
+/// let scanner = rules.scanner()?;
+/// let core = ElfCoreMemoryIterator::new(std::fs::read("core.1234").unwrap())?;
+/// let results = scanner.scan_mem_blocks(core)?;
+/// # Ok::<(), yara::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct ElfCoreMemoryIterator<D> {
+    data: D,
+    segments: Vec<ElfSegment>,
+    /// Index of the next segment.
+    next: usize,
+}
+
+impl<D: AsRef<[u8]>> ElfCoreMemoryIterator<D> {
+    /// Parse the segments of the ELF core dump `data`.
+    pub fn new(data: D) -> Result<Self, Error> {
+        let segments = parse(data.as_ref())?;
+        Ok(Self {
+            data,
+            segments,
+            next: 0,
+        })
+    }
+
+    /// The `PT_LOAD` segments of the dump.
+    pub fn segments(&self) -> &[ElfSegment] {
+        &self.segments
+    }
+
+    fn next_block(&mut self) -> Option<MemoryBlock<'_>> {
+        let data = self.data.as_ref();
+        while let Some(segment) = self.segments.get(self.next) {
+            self.next += 1;
+            let content = dumped(data, segment.offset, segment.file_size);
+            if !content.is_empty() {
+                return Some(MemoryBlock::new(segment.vaddr, content));
+            }
+        }
+        None
+    }
+}
+
+impl<D: AsRef<[u8]>> MemoryBlockIterator for ElfCoreMemoryIterator<D> {
+    fn first(&mut self) -> Option<MemoryBlock<'_>> {
+        self.next = 0;
+        self.next_block()
+    }
+
+    fn next(&mut self) -> Option<MemoryBlock<'_>> {
+        self.next_block()
+    }
+}
+
+/// Class and encoding of an ELF file.
+#[derive(Clone, Copy)]
+struct Elf {
+    is_64: bool,
+    little_endian: bool,
+}
+
+impl Elf {
+    fn u16(self, data: &[u8], offset: u64) -> Option<u16> {
+        let bytes = read_bytes(data, offset)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(self, data: &[u8], offset: u64) -> Option<u32> {
+        let bytes = read_bytes(data, offset)?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn u64(self, data: &[u8], offset: u64) -> Option<u64> {
+        let bytes = read_bytes(data, offset)?;
+        Some(if self.little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        })
+    }
+
+    /// Read an address or an offset, whose size depends on the class.
+    fn word(self, data: &[u8], offset: u64) -> Option<u64> {
+        if self.is_64 {
+            self.u64(data, offset)
+        } else {
+            self.u32(data, offset).map(u64::from)
+        }
+    }
+}
+
+fn parse(data: &[u8]) -> Result<Vec<ElfSegment>, Error> {
+    if !data.starts_with(b"\x7fELF") {
+        return Err(invalid_dump("not an ELF file"));
+    }
+    let is_64 = match data.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => return Err(invalid_dump("invalid ELF class")),
+    };
+    let little_endian = match data.get(5) {
+        Some(1) => true,
+        Some(2) => false,
+        _ => return Err(invalid_dump("invalid ELF data encoding")),
+    };
+    let elf = Elf {
+        is_64,
+        little_endian,
+    };
+    parse_segments(elf, data).ok_or_else(|| invalid_dump("truncated ELF core"))?
+}
+
+/// Parse the program headers, returning `None` if they are truncated.
+fn parse_segments(elf: Elf, data: &[u8]) -> Option<Result<Vec<ElfSegment>, Error>> {
+    if elf.u16(data, 16)? != ET_CORE {
+        return Some(Err(invalid_dump("not an ELF core file")));
+    }
+    // Offsets of e_phoff, e_shoff, e_phentsize and e_phnum.
+    let (phoff, shoff, phentsize, phnum) = if elf.is_64 {
+        (32, 40, 54, 56)
+    } else {
+        (28, 32, 42, 44)
+    };
+    let phoff = elf.word(data, phoff)?;
+    let phentsize = u64::from(elf.u16(data, phentsize)?);
+    let phnum = match elf.u16(data, phnum)? {
+        // sh_info of the first section header.
+        PN_XNUM => {
+            let shoff = elf.word(data, shoff)?;
+            u64::from(elf.u32(data, shoff.checked_add(if elf.is_64 { 44 } else { 28 })?)?)
+        }
+        phnum => u64::from(phnum),
+    };
+    if phentsize < if elf.is_64 { 56 } else { 32 } {
+        return Some(Err(invalid_dump("invalid ELF program header size")));
+    }
+
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        let header = phoff.checked_add(i.checked_mul(phentsize)?)?;
+        if elf.u32(data, header)? != PT_LOAD {
+            continue;
+        }
+        segments.push(if elf.is_64 {
+            ElfSegment {
+                flags: elf.u32(data, header.checked_add(4)?)?,
+                offset: elf.u64(data, header.checked_add(8)?)?,
+                vaddr: elf.u64(data, header.checked_add(16)?)?,
+                file_size: elf.u64(data, header.checked_add(32)?)?,
+                memory_size: elf.u64(data, header.checked_add(40)?)?,
+            }
+        } else {
+            ElfSegment {
+                offset: u64::from(elf.u32(data, header.checked_add(4)?)?),
+                vaddr: u64::from(elf.u32(data, header.checked_add(8)?)?),
+                file_size: u64::from(elf.u32(data, header.checked_add(16)?)?),
+                memory_size: u64::from(elf.u32(data, header.checked_add(20)?)?),
+                flags: elf.u32(data, header.checked_add(24)?)?,
+            }
+        });
+    }
+    Some(Ok(segments))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::collect_blocks;
+
+    /// Build a little-endian ELF64 core with a `PT_NOTE` segment and a `PT_LOAD` segment
+    /// for each of `segments`.
+    fn elf64_core(segments: &[(u64, &[u8])]) -> Vec<u8> {
+        let phnum = segments.len() + 1;
+        let mut data = vec![0; 64];
+        data[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        data[6] = 1;
+        data[16..18].copy_from_slice(&ET_CORE.to_le_bytes());
+        data[32..40].copy_from_slice(&64u64.to_le_bytes());
+        data[54..56].copy_from_slice(&56u16.to_le_bytes());
+        data[56..58].copy_from_slice(&(phnum as u16).to_le_bytes());
+
+        let mut offset = 64 + 56 * phnum as u64;
+        let mut headers = vec![0; 56];
+        headers[..4].copy_from_slice(&4u32.to_le_bytes());
+        for (vaddr, content) in segments {
+            let mut header = vec![0; 56];
+            header[..4].copy_from_slice(&PT_LOAD.to_le_bytes());
+            header[4..8].copy_from_slice(&6u32.to_le_bytes());
+            header[8..16].copy_from_slice(&offset.to_le_bytes());
+            header[16..24].copy_from_slice(&vaddr.to_le_bytes());
+            header[32..40].copy_from_slice(&(content.len() as u64).to_le_bytes());
+            header[40..48].copy_from_slice(&(content.len() as u64).to_le_bytes());
+            headers.extend(header);
+            offset += content.len() as u64;
+        }
+        data.extend(headers);
+        for (_, content) in segments {
+            data.extend_from_slice(content);
+        }
+        data
+    }
+
+    #[test]
+    fn elf64_segments() {
+        let core = elf64_core(&[(0x400000, b"abcd"), (0x7fff0000, b""), (0x7ffe0000, b"xyz")]);
+        let mut iter = ElfCoreMemoryIterator::new(&core).unwrap();
+        assert_eq!(iter.segments().len(), 3);
+        assert_eq!(iter.segments()[0].flags, 6);
+        assert_eq!(
+            collect_blocks(&mut iter),
+            vec![(0x400000, b"abcd".to_vec()), (0x7ffe0000, b"xyz".to_vec())]
+        );
+
+        // Truncated content.
+        let mut iter = ElfCoreMemoryIterator::new(&core[..core.len() - 1]).unwrap();
+        assert_eq!(
+            collect_blocks(&mut iter),
+            vec![(0x400000, b"abcd".to_vec()), (0x7ffe0000, b"xy".to_vec())]
+        );
+    }
+
+    #[test]
+    fn elf32_big_endian_segments() {
+        let mut core = vec![0; 52 + 32 + 4];
+        core[..6].copy_from_slice(b"\x7fELF\x01\x02");
+        core[16..18].copy_from_slice(&ET_CORE.to_be_bytes());
+        core[28..32].copy_from_slice(&52u32.to_be_bytes());
+        core[42..44].copy_from_slice(&32u16.to_be_bytes());
+        core[44..46].copy_from_slice(&1u16.to_be_bytes());
+        core[52..56].copy_from_slice(&PT_LOAD.to_be_bytes());
+        core[56..60].copy_from_slice(&84u32.to_be_bytes());
+        core[60..64].copy_from_slice(&0x8000u32.to_be_bytes());
+        core[68..72].copy_from_slice(&4u32.to_be_bytes());
+        core[72..76].copy_from_slice(&4u32.to_be_bytes());
+        core[84..].copy_from_slice(b"rust");
+
+        let mut iter = ElfCoreMemoryIterator::new(core).unwrap();
+        assert_eq!(collect_blocks(&mut iter), vec![(0x8000, b"rust".to_vec())]);
+    }
+
+    #[test]
+    fn scan() {
+        let rules = crate::Compiler::new()
+            .unwrap()
+            .add_rules_str("rule rust { strings: $a = \"rust\" condition: $a }")
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let core = elf64_core(&[(0x7f0000001000, b"...rust...")]);
+        let iter = ElfCoreMemoryIterator::new(core).unwrap();
+        let results = rules.scanner().unwrap().scan_mem_blocks(iter).unwrap();
+        let m = &results[0].strings[0].matches[0];
+        assert_eq!((m.base, m.offset), (0x7f0000001000, 3));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(ElfCoreMemoryIterator::new(b"MZ").is_err());
+        let core = elf64_core(&[(0x1000, b"ab")]);
+        let mut executable = core.clone();
+        executable[16] = 2;
+        assert!(ElfCoreMemoryIterator::new(executable).is_err());
+        assert!(ElfCoreMemoryIterator::new(&core[..60]).is_err());
+        // Truncated PT_LOAD header.
+        assert!(ElfCoreMemoryIterator::new(&core[..150]).is_err());
+        // Section headers at an offset overflowing with the offset of sh_info.
+        let mut overflow = core.clone();
+        overflow[40..48].copy_from_slice(&u64::MAX.to_le_bytes());
+        overflow[56..58].copy_from_slice(&PN_XNUM.to_le_bytes());
+        assert!(ElfCoreMemoryIterator::new(overflow).is_err());
+    }
+}
@@ -0,0 +1,393 @@
+use crate::errors::*;
+use crate::internals::{MemoryBlock, MemoryBlockIterator, MemoryBlockIteratorSized};
+use crate::Match;
+
+use super::{dumped, invalid_dump, read_bytes};
+
+const SIGNATURE: &[u8] = b"MDMP";
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const MEMORY64_LIST_STREAM: u32 = 9;
+/// Size of a `MINIDUMP_MODULE`.
+const MODULE_SIZE: u64 = 108;
+
+/// A memory range saved in a minidump.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MinidumpMemoryRange {
+    /// Virtual address of the range in the process.
+    pub address: u64,
+    /// Offset of the content of the range in the dump.
+    pub offset: u64,
+    /// Size of the range.
+    pub size: u64,
+}
+
+/// A module loaded in the process, from the module list of a minidump.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MinidumpModule {
+    /// Virtual address of the image of the module.
+    pub base: u64,
+    /// Size of the image of the module.
+    pub size: u64,
+    /// Full path of the module, e.g. `C:\Windows\System32\ntdll.dll`.
+    pub name: String,
+}
+
+/// A [`MemoryBlockIteratorSized`] over the memory ranges of a Windows minidump.
+///
+/// Each range of the `MemoryListStream` and `Memory64ListStream` streams is a block whose
+/// base is its virtual address, in increasing address order. Ranges truncated in the dump
+/// are scanned up to its end. `filesize` is the total size of the ranges.
+///
+/// The module list of the dump is kept, to find the module a match is in. As scanning
+/// only borrows the iterator when given `&mut iter`, it can be used after the scan.
+///
+/// # Example
+///
+/// ```no_run
+/// # use yara::Compiler;
+/// use yara::dump::MinidumpMemoryIterator;
+///
+/// # let rules = Compiler::new()?.add_rules_str("rule r { condition: true }")?.compile_rules()?;
+/// let scanner = rules.scanner()?;
+/// let mut dump = MinidumpMemoryIterator::new(std::fs::read("lsass.dmp").unwrap())?;
+/// for rule in scanner.scan_mem_blocks_sized(&mut dump)? {
+///     for m in rule.strings.iter().flat_map(|s| &s.matches) {
+///         let module = dump.module(m).map_or("-", |module| &module.name);
+///         println!("{} at {:#x} ({})", rule.identifier, m.base + m.offset, module);
+///     }
+/// }
+/// # Ok::<(), yara::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct MinidumpMemoryIterator<D> {
+    data: D,
+    ranges: Vec<MinidumpMemoryRange>,
+    modules: Vec<MinidumpModule>,
+    /// Index of the next range.
+    next: usize,
+}
+
+impl<D: AsRef<[u8]>> MinidumpMemoryIterator<D> {
+    /// Parse the memory ranges and the modules of the minidump `data`.
+    pub fn new(data: D) -> Result<Self, Error> {
+        let (ranges, modules) = parse(data.as_ref())?;
+        Ok(Self {
+            data,
+            ranges,
+            modules,
+            next: 0,
+        })
+    }
+
+    /// The memory ranges of the dump, sorted by address.
+    pub fn ranges(&self) -> &[MinidumpMemoryRange] {
+        &self.ranges
+    }
+
+    /// The modules of the dump, sorted by address.
+    pub fn modules(&self) -> &[MinidumpModule] {
+        &self.modules
+    }
+
+    /// Get the module containing the address `address`.
+    pub fn module_at(&self, address: u64) -> Option<&MinidumpModule> {
+        let index = self
+            .modules
+            .partition_point(|module| module.base <= address);
+        index
+            .checked_sub(1)
+            .map(|index| &self.modules[index])
+            .filter(|module| address - module.base < module.size)
+    }
+
+    /// Get the module a match was found in.
+    pub fn module(&self, m: &Match) -> Option<&MinidumpModule> {
+        self.module_at((m.base as u64).checked_add(m.offset as u64)?)
+    }
+
+    fn next_block(&mut self) -> Option<MemoryBlock<'_>> {
+        let data = self.data.as_ref();
+        while let Some(range) = self.ranges.get(self.next) {
+            self.next += 1;
+            let content = dumped(data, range.offset, range.size);
+            if !content.is_empty() {
+                return Some(MemoryBlock::new(range.address, content));
+            }
+        }
+        None
+    }
+}
+
+impl<D: AsRef<[u8]>> MemoryBlockIterator for MinidumpMemoryIterator<D> {
+    fn first(&mut self) -> Option<MemoryBlock<'_>> {
+        self.next = 0;
+        self.next_block()
+    }
+
+    fn next(&mut self) -> Option<MemoryBlock<'_>> {
+        self.next_block()
+    }
+}
+
+impl<D: AsRef<[u8]>> MemoryBlockIteratorSized for MinidumpMemoryIterator<D> {
+    fn file_size(&mut self) -> u64 {
+        self.ranges
+            .iter()
+            .fold(0, |size, range| size.saturating_add(range.size))
+    }
+}
+
+fn u32(data: &[u8], offset: u64) -> Option<u32> {
+    read_bytes(data, offset).map(u32::from_le_bytes)
+}
+
+fn u64(data: &[u8], offset: u64) -> Option<u64> {
+    read_bytes(data, offset).map(u64::from_le_bytes)
+}
+
+fn parse(data: &[u8]) -> Result<(Vec<MinidumpMemoryRange>, Vec<MinidumpModule>), Error> {
+    if !data.starts_with(SIGNATURE) {
+        return Err(invalid_dump("not a minidump"));
+    }
+    parse_streams(data).ok_or_else(|| invalid_dump("truncated minidump"))
+}
+
+/// Parse the streams of the dump, returning `None` if they are truncated.
+fn parse_streams(data: &[u8]) -> Option<(Vec<MinidumpMemoryRange>, Vec<MinidumpModule>)> {
+    let count = u64::from(u32(data, 8)?);
+    let directory = u64::from(u32(data, 12)?);
+    let mut ranges = Vec::new();
+    let mut modules = Vec::new();
+    for i in 0..count {
+        let entry = directory.checked_add(i.checked_mul(12)?)?;
+        let rva = u64::from(u32(data, entry.checked_add(8)?)?);
+        match u32(data, entry)? {
+            MEMORY_LIST_STREAM => parse_memory_list(data, rva, &mut ranges)?,
+            MEMORY64_LIST_STREAM => parse_memory64_list(data, rva, &mut ranges)?,
+            MODULE_LIST_STREAM => parse_module_list(data, rva, &mut modules)?,
+            _ => {}
+        }
+    }
+    ranges.sort_by_key(|range| range.address);
+    modules.sort_by_key(|module| module.base);
+    Some((ranges, modules))
+}
+
+fn parse_memory_list(data: &[u8], rva: u64, ranges: &mut Vec<MinidumpMemoryRange>) -> Option<()> {
+    let count = u64::from(u32(data, rva)?);
+    for i in 0..count {
+        // MINIDUMP_MEMORY_DESCRIPTOR, after the 4 bytes of the count.
+        let descriptor = rva.checked_add(4)?.checked_add(i.checked_mul(16)?)?;
+        ranges.push(MinidumpMemoryRange {
+            address: u64(data, descriptor)?,
+            size: u64::from(u32(data, descriptor.checked_add(8)?)?),
+            offset: u64::from(u32(data, descriptor.checked_add(12)?)?),
+        });
+    }
+    Some(())
+}
+
+fn parse_memory64_list(data: &[u8], rva: u64, ranges: &mut Vec<MinidumpMemoryRange>) -> Option<()> {
+    let count = u64(data, rva)?;
+    // The contents of the ranges follow each other from the base RVA.
+    let mut offset = u64(data, rva.checked_add(8)?)?;
+    for i in 0..count {
+        // MINIDUMP_MEMORY_DESCRIPTOR64, after the count and the base RVA.
+        let descriptor = rva.checked_add(16)?.checked_add(i.checked_mul(16)?)?;
+        let size = u64(data, descriptor.checked_add(8)?)?;
+        ranges.push(MinidumpMemoryRange {
+            address: u64(data, descriptor)?,
+            offset,
+            size,
+        });
+        offset = offset.saturating_add(size);
+    }
+    Some(())
+}
+
+fn parse_module_list(data: &[u8], rva: u64, modules: &mut Vec<MinidumpModule>) -> Option<()> {
+    let count = u64::from(u32(data, rva)?);
+    for i in 0..count {
+        // MINIDUMP_MODULE, after the 4 bytes of the count.
+        let module = rva
+            .checked_add(4)?
+            .checked_add(i.checked_mul(MODULE_SIZE)?)?;
+        let name = u64::from(u32(data, module.checked_add(20)?)?);
+        modules.push(MinidumpModule {
+            base: u64(data, module)?,
+            size: u64::from(u32(data, module.checked_add(8)?)?),
+            name: parse_string(data, name).unwrap_or_default(),
+        });
+    }
+    Some(())
+}
+
+/// Parse the UTF-16 `MINIDUMP_STRING` at `rva`.
+fn parse_string(data: &[u8], rva: u64) -> Option<String> {
+    let length = u64::from(u32(data, rva)?);
+    let mut units = Vec::new();
+    for i in 0..length / 2 {
+        units.push(u16::from_le_bytes(read_bytes(
+            data,
+            rva.checked_add(4)?.checked_add(i.checked_mul(2)?)?,
+        )?));
+    }
+    Some(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::internals::collect_blocks;
+
+    /// Build a minidump with a module list, a memory list and a memory64 list.
+    fn minidump(
+        modules: &[(u64, u32, &str)],
+        memory: &[(u64, &[u8])],
+        memory64: &[(u64, &[u8])],
+    ) -> Vec<u8> {
+        let mut dump = vec![0; 32 + 3 * 12];
+        dump[..8].copy_from_slice(b"MDMP\x93\xa7\x00\x00");
+        dump[8..12].copy_from_slice(&3u32.to_le_bytes());
+        dump[12..16].copy_from_slice(&32u32.to_le_bytes());
+        let mut directory = Vec::new();
+
+        let names: Vec<_> = modules
+            .iter()
+            .map(|(_, _, name)| {
+                let rva = dump.len() as u32;
+                let name: Vec<u16> = name.encode_utf16().collect();
+                dump.extend_from_slice(&(name.len() as u32 * 2).to_le_bytes());
+                dump.extend(name.iter().flat_map(|unit| unit.to_le_bytes()));
+                rva
+            })
+            .collect();
+        directory.push((MODULE_LIST_STREAM, dump.len() as u32));
+        dump.extend_from_slice(&(modules.len() as u32).to_le_bytes());
+        for ((base, size, _), name) in modules.iter().zip(names) {
+            let mut module = vec![0; MODULE_SIZE as usize];
+            module[..8].copy_from_slice(&base.to_le_bytes());
+            module[8..12].copy_from_slice(&size.to_le_bytes());
+            module[20..24].copy_from_slice(&name.to_le_bytes());
+            dump.extend(module);
+        }
+
+        let contents: Vec<_> = memory
+            .iter()
+            .map(|(_, content)| {
+                let rva = dump.len() as u32;
+                dump.extend_from_slice(content);
+                rva
+            })
+            .collect();
+        directory.push((MEMORY_LIST_STREAM, dump.len() as u32));
+        dump.extend_from_slice(&(memory.len() as u32).to_le_bytes());
+        for ((address, content), rva) in memory.iter().zip(contents) {
+            dump.extend_from_slice(&address.to_le_bytes());
+            dump.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            dump.extend_from_slice(&rva.to_le_bytes());
+        }
+
+        directory.push((MEMORY64_LIST_STREAM, dump.len() as u32));
+        let base_rva = dump.len() + 16 + 16 * memory64.len();
+        dump.extend_from_slice(&(memory64.len() as u64).to_le_bytes());
+        dump.extend_from_slice(&(base_rva as u64).to_le_bytes());
+        for (address, content) in memory64 {
+            dump.extend_from_slice(&address.to_le_bytes());
+            dump.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        }
+        for (_, content) in memory64 {
+            dump.extend_from_slice(content);
+        }
+
+        for (i, (stream_type, rva)) in directory.into_iter().enumerate() {
+            let entry = 32 + 12 * i;
+            dump[entry..entry + 4].copy_from_slice(&stream_type.to_le_bytes());
+            dump[entry + 8..entry + 12].copy_from_slice(&rva.to_le_bytes());
+        }
+        dump
+    }
+
+    #[test]
+    fn memory_ranges() {
+        let dump = minidump(
+            &[],
+            &[(0x7ffe0000, b"stack")],
+            &[(0x10000, b"abcd"), (0x20000, b""), (0x140001000, b"xyz")],
+        );
+        let mut iter = MinidumpMemoryIterator::new(&dump).unwrap();
+        assert_eq!(iter.ranges().len(), 4);
+        assert_eq!(iter.file_size(), 12);
+        assert_eq!(
+            collect_blocks(&mut iter),
+            vec![
+                (0x10000, b"abcd".to_vec()),
+                (0x7ffe0000, b"stack".to_vec()),
+                (0x140001000, b"xyz".to_vec()),
+            ]
+        );
+
+        // Truncated content.
+        let mut iter = MinidumpMemoryIterator::new(&dump[..dump.len() - 1]).unwrap();
+        assert_eq!(collect_blocks(&mut iter)[2], (0x140001000, b"xy".to_vec()));
+    }
+
+    #[test]
+    fn modules() {
+        let dump = minidump(
+            &[
+                (0x7ff800000000, 0x1000, r"C:\Windows\System32\ntdll.dll"),
+                (0x140000000, 0x2000, r"C:\app.exe"),
+            ],
+            &[],
+            &[],
+        );
+        let iter = MinidumpMemoryIterator::new(dump).unwrap();
+        assert_eq!(iter.modules()[0].name, r"C:\app.exe");
+        assert_eq!(iter.module_at(0x13fffffff), None);
+        assert_eq!(iter.module_at(0x140001fff).unwrap().base, 0x140000000);
+        assert_eq!(iter.module_at(0x140002000), None);
+        assert_eq!(
+            iter.module_at(0x7ff800000010).unwrap().name,
+            r"C:\Windows\System32\ntdll.dll"
+        );
+    }
+
+    #[test]
+    fn scan() {
+        let rules = crate::Compiler::new()
+            .unwrap()
+            .add_rules_str("rule rust { strings: $a = \"rust\" condition: $a }")
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let dump = minidump(
+            &[(0x140000000, 0x10000, "app.exe")],
+            &[],
+            &[(0x140001000, b"...rust...")],
+        );
+        let mut iter = MinidumpMemoryIterator::new(dump).unwrap();
+        let results = rules
+            .scanner()
+            .unwrap()
+            .scan_mem_blocks_sized(&mut iter)
+            .unwrap();
+        let m = &results[0].strings[0].matches[0];
+        assert_eq!((m.base, m.offset), (0x140001000, 3));
+        assert_eq!(iter.module(m).unwrap().name, "app.exe");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(MinidumpMemoryIterator::new(b"\x7fELF").is_err());
+        let dump = minidump(&[], &[(0x1000, b"ab")], &[]);
+        assert!(MinidumpMemoryIterator::new(&dump[..40]).is_err());
+
+        // Memory64 list with more ranges than the dump can hold.
+        let mut dump = minidump(&[], &[], &[(0x1000, b"ab")]);
+        let rva = u32::from_le_bytes(dump[64..68].try_into().unwrap()) as usize;
+        dump[rva..rva + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(MinidumpMemoryIterator::new(dump).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+//! Scanning of memory dumps as the process memory they describe.
+//!
+//! The iterators of this module expose the memory ranges saved in a dump as
+//! [`MemoryBlock`](crate::MemoryBlock)s whose base is their original virtual address, so
+//! the matches of [`Scanner::scan_mem_blocks`](crate::Scanner::scan_mem_blocks) line up
+//! with those of [`Scanner::scan_process`](crate::Scanner::scan_process).
+//!
+//! ELF core dumps and Windows minidumps are supported, whatever the platform.
+//!
+//! The dumps are parsed from memory, for example a file read with [`std::fs::read`].
+
+pub use self::elf::{ElfCoreMemoryIterator, ElfSegment};
+pub use self::minidump::{MinidumpMemoryIterator, MinidumpMemoryRange, MinidumpModule};
+
+mod elf;
+mod minidump;
+
+use std::io;
+
+use crate::errors::*;
+
+/// Error for a dump that can not be parsed.
+fn invalid_dump(message: &str) -> Error {
+    let error = io::Error::new(io::ErrorKind::InvalidData, message);
+    IoError::new(error, IoErrorKind::ParsingDump).into()
+}
+
+/// Read `N` bytes at `offset`.
+fn read_bytes<const N: usize>(data: &[u8], offset: u64) -> Option<[u8; N]> {
+    let offset = usize::try_from(offset).ok()?;
+    data.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+/// Get the `size` bytes at `offset`, truncated to the end of the dump.
+fn dumped(data: &[u8], offset: u64, size: u64) -> &[u8] {
+    let start = usize::try_from(offset).map_or(data.len(), |offset| offset.min(data.len()));
+    let size = usize::try_from(size).unwrap_or(usize::MAX);
+    &data[start..start.saturating_add(size).min(data.len())]
+}
@@ -2,6 +2,7 @@ use std::fmt;
 
 pub use yara_sys::CompileErrorLevel;
 pub use yara_sys::Error as YaraErrorKind;
+pub use yara_sys::ExternalVariableType;
 
 use std::error::Error as StdError;
 
@@ -9,6 +10,7 @@ use thiserror::Error as ThisError;
 
 /// A wrapper around the kinds of errors that can happen in the library.
 #[derive(Debug, ThisError)]
+#[non_exhaustive]
 pub enum Error {
     /// An IO error.
     #[error("{0}")]
@@ -18,9 +20,25 @@ pub enum Error {
     /// See [`YaraError`] and [`yara_sys::Error`].
     #[error("{0}")]
     Yara(#[from] YaraError),
+    /// A Yara error of a scan, with the rule that caused it.
+    ///
+    /// See [`ScanError`].
+    #[error("{0}")]
+    Scan(#[from] ScanError),
     /// A rule compilation error.
     #[error("{0}")]
     Compile(#[from] CompileErrors),
+    /// An external variable that does not match its declaration.
+    #[error("{0}")]
+    Variable(#[from] VariableError),
+    /// The scan was cancelled with a [`CancellationToken`](crate::CancellationToken).
+    #[error("Scan cancelled")]
+    Cancelled,
+    /// An archive member exceeding the limits of the
+    /// [`ArchiveScanner`](crate::archive::ArchiveScanner).
+    #[cfg(feature = "archive")]
+    #[error("{0}")]
+    Archive(#[from] ArchiveError),
 }
 
 #[derive(Debug, ThisError)]
@@ -51,6 +69,20 @@ pub enum IoErrorKind {
     ReadingRules,
     #[error("Error while writing rules stream")]
     WritingRules,
+    #[error("Error while opening scan root")]
+    OpeningScanRoot,
+    #[error("Error while reading directory")]
+    ReadingDirectory,
+    #[error("Error while reading scanned data")]
+    ReadingScanData,
+    #[error("Error while unpacking archive")]
+    ReadingArchive,
+    #[error("Error while reading process memory maps")]
+    ReadingProcessMaps,
+    #[error("Error while opening process memory")]
+    OpeningProcessMemory,
+    #[error("Error while parsing memory dump")]
+    ParsingDump,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ThisError)]
@@ -65,6 +97,89 @@ impl From<YaraErrorKind> for YaraError {
     }
 }
 
+/// A [`ScanTimeout`](YaraErrorKind::ScanTimeout) or
+/// [`TooManyMatches`](YaraErrorKind::TooManyMatches) error of a scan, with the rule Yara
+/// was matching when it happened.
+///
+/// The scans returning an [`Error`] report these errors as [`Error::Scan`] rather than
+/// [`Error::Yara`]. The ones returning a [`YaraError`] only report the kind of the error.
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+pub struct ScanError {
+    /// Kind of the error.
+    pub kind: YaraErrorKind,
+    /// The rule and string that caused the error, if Yara knows it.
+    pub rule: Option<ErrorRule>,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(rule) = &self.rule {
+            write!(f, " {rule}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The rule that caused a scan error.
+///
+/// See [`ScanError`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ErrorRule {
+    /// Namespace of the rule.
+    pub namespace: String,
+    /// Name of the rule.
+    pub identifier: String,
+    /// Identifier of the string that was being matched, if any.
+    pub string: Option<String>,
+}
+
+impl fmt::Display for ErrorRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "in rule {}:{}", self.namespace, self.identifier)?;
+        if let Some(string) = &self.string {
+            write!(f, ", string {string}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error when defining an external variable that does not match its declaration in the rules.
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+pub enum VariableError {
+    /// The variable was not declared when compiling the rules.
+    #[error("Undefined external variable {0}")]
+    Undefined(String),
+    /// The variable was declared with another type.
+    #[error("External variable {identifier} is of type {expected:?}, not {actual:?}")]
+    TypeMismatch {
+        identifier: String,
+        expected: ExternalVariableType,
+        actual: ExternalVariableType,
+    },
+}
+
+/// Error when an archive member exceeds the limits of the
+/// [`ArchiveScanner`](crate::archive::ArchiveScanner).
+#[cfg(feature = "archive")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ThisError)]
+pub enum ArchiveError {
+    /// The member is larger than the maximum member size.
+    #[error("Archive member larger than {0} bytes")]
+    MemberTooLarge(u64),
+    /// The member is compressed more than the maximum compression ratio.
+    #[error("Archive member compression ratio above {0}")]
+    RatioExceeded(u64),
+    /// The members of the archive are larger than the maximum total size.
+    #[error("Archive members larger than {0} bytes in total")]
+    TotalSizeExceeded(u64),
+}
+
+/// Error when parsing a [`MetadataPredicate`](crate::MetadataPredicate).
+#[derive(Clone, Debug, Eq, PartialEq, ThisError)]
+#[error("Invalid metadata predicate `{0}`")]
+pub struct MetadataPredicateError(pub String);
+
 /// The errors and warning returned during the rules compilation.
 #[derive(Debug)]
 pub struct CompileErrors {
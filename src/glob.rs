@@ -0,0 +1,90 @@
+/// Match `text` against a glob `pattern`.
+///
+/// `?` matches any character except `/`, `*` any sequence of characters except `/`,
+/// `**` any sequence of characters and `**/` zero or more directories.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let tokens = tokenize(pattern);
+    let text: Vec<char> = text.chars().collect();
+
+    // matches[i][j] is whether tokens[i..] matches text[j..].
+    let mut matches = vec![vec![false; text.len() + 1]; tokens.len() + 1];
+    matches[tokens.len()][text.len()] = true;
+    for i in (0..tokens.len()).rev() {
+        // Whether tokens[i + 1..] matches right after a `/` of text[j..], for `**/`.
+        let mut after_slash = false;
+        for j in (0..=text.len()).rev() {
+            let next = text.get(j);
+            matches[i][j] = match tokens[i] {
+                Token::Char(c) => next == Some(&c) && matches[i + 1][j + 1],
+                Token::AnyChar => next.map_or(false, |&c| c != '/') && matches[i + 1][j + 1],
+                Token::Star => {
+                    matches[i + 1][j] || (next.map_or(false, |&c| c != '/') && matches[i][j + 1])
+                }
+                Token::GlobStar => matches[i + 1][j] || (next.is_some() && matches[i][j + 1]),
+                Token::GlobStarDir => {
+                    after_slash |= next == Some(&'/') && matches[i + 1][j + 1];
+                    matches[i + 1][j] || after_slash
+                }
+            };
+        }
+    }
+
+    matches[0][0]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    AnyChar,
+    Star,
+    GlobStar,
+    GlobStarDir,
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '?' => Token::AnyChar,
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    Token::GlobStarDir
+                } else {
+                    Token::GlobStar
+                }
+            }
+            '*' => Token::Star,
+            c => Token::Char(c),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod test {
+    use super::glob_match;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*.exe", "a.exe"));
+        assert!(!glob_match("*.exe", "dir/a.exe"));
+        assert!(!glob_match("*.exe", "a.exe.txt"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "a/c"));
+        assert!(glob_match("dir/*", "dir/a"));
+        assert!(!glob_match("dir/*", "dir/a/b"));
+        assert!(glob_match("dir/**", "dir/a/b"));
+        assert!(glob_match("**/node_modules", "node_modules"));
+        assert!(glob_match("**/node_modules", "a/b/node_modules"));
+        assert!(!glob_match("**/node_modules", "a/bnode_modules"));
+        assert!(glob_match("a/**/b", "a/b"));
+        assert!(glob_match("a/**/b", "a/x/y/b"));
+        assert!(!glob_match("a/**/b", "a/xb"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+    }
+}
@@ -30,7 +30,7 @@ pub fn compiler_add_string(
     compiler: *mut YR_COMPILER,
     string: &str,
     namespace: Option<&str>,
-) -> Result<(), Error> {
+) -> Result<Vec<CompileError>, Error> {
     let string = CString::new(string).unwrap();
     let namespace = namespace.map(|n| CString::new(n).unwrap());
     let mut errors = Vec::<CompileError>::new();
@@ -52,9 +52,13 @@ pub fn compiler_add_string(
     compile_result(result, errors)
 }
 
-fn compile_result(compile_result: i32, messages: Vec<CompileError>) -> Result<(), Error> {
+/// Returns the warnings on success, or all the messages if there is an error.
+fn compile_result(
+    compile_result: i32,
+    messages: Vec<CompileError>,
+) -> Result<Vec<CompileError>, Error> {
     if compile_result == 0 || messages.iter().all(|c| c.level != CompileErrorLevel::Error) {
-        Ok(())
+        Ok(messages)
     } else {
         Err(CompileErrors::new(messages).into())
     }
@@ -66,7 +70,7 @@ pub fn compiler_add_file<P: AsRef<Path>, F: AsRawFd>(
     file: &F,
     path: P,
     namespace: Option<&str>,
-) -> Result<(), Error> {
+) -> Result<Vec<CompileError>, Error> {
     let path = CString::new(path.as_ref().as_os_str().to_str().unwrap()).unwrap();
     let namespace = namespace.map(|n| CString::new(n).unwrap());
     let mut errors = Vec::<CompileError>::new();
@@ -96,7 +100,7 @@ pub fn compiler_add_file<P: AsRef<Path>, F: AsRawHandle>(
     file: &F,
     path: P,
     namespace: Option<&str>,
-) -> Result<(), Error> {
+) -> Result<Vec<CompileError>, Error> {
     let path = CString::new(path.as_ref().as_os_str().to_str().unwrap()).unwrap();
     let namespace = namespace.map(|n| CString::new(n).unwrap());
     let mut errors = Vec::<CompileError>::new();
@@ -16,6 +16,16 @@ impl<'a> MemoryBlock<'a> {
         Self { base, data }
     }
 
+    /// Address of the block.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Content of the block.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
     fn into_yara(self) -> YR_MEMORY_BLOCK {
         YR_MEMORY_BLOCK {
             base: self.base,
@@ -196,3 +206,15 @@ unsafe extern "C" fn mem_block_iterator_file_size<T: MemoryBlockIteratorSized>(
 unsafe extern "C" fn mem_block_fetch_data(mem_block: *mut YR_MEMORY_BLOCK) -> *const u8 {
     (*mem_block).context as *const u8
 }
+
+/// Collect the bases and the data of the blocks of `iter`.
+#[cfg(test)]
+pub(crate) fn collect_blocks(mut iter: impl MemoryBlockIterator) -> Vec<(u64, Vec<u8>)> {
+    let mut blocks = Vec::new();
+    let mut block = iter.first().map(|b| (b.base(), b.data().to_vec()));
+    while let Some(b) = block {
+        blocks.push(b);
+        block = iter.next().map(|b| (b.base(), b.data().to_vec()));
+    }
+    blocks
+}
@@ -18,6 +18,7 @@ pub mod string;
 mod compiler;
 pub mod configuration;
 mod iterator;
+pub mod module;
 mod module_import;
 mod object;
 mod rules;
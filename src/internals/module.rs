@@ -0,0 +1,132 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use yara_sys::{YR_MODULE_FUNC, YR_OBJECT};
+
+use crate::errors::*;
+
+/// printf-like format used to pass a field path to the `yr_object_set_*` functions,
+/// so `%` in the path is not interpreted.
+const FIELD_FORMAT: &[u8] = b"%s\0";
+
+/// Convert an identifier or a field path to a C string.
+pub fn to_cstring(s: &str) -> Result<CString, YaraError> {
+    CString::new(s)
+        .map_err(|_| YaraErrorKind::Unknown(yara_sys::ERROR_INVALID_ARGUMENT as i32).into())
+}
+
+pub fn object_create(
+    type_: u32,
+    identifier: &CStr,
+    parent: *mut YR_OBJECT,
+) -> Result<*mut YR_OBJECT, YaraError> {
+    let mut object = ptr::null_mut();
+    let result = unsafe {
+        yara_sys::yr_object_create(type_ as i8, identifier.as_ptr(), parent, &mut object)
+    };
+
+    yara_sys::Error::from_code(result)
+        .map(|()| object)
+        .map_err(Into::into)
+}
+
+pub fn object_function_create(
+    identifier: &CStr,
+    arguments_fmt: &CStr,
+    return_fmt: &CStr,
+    func: YR_MODULE_FUNC,
+    parent: *mut YR_OBJECT,
+) -> Result<*mut YR_OBJECT, YaraError> {
+    let mut function = ptr::null_mut();
+    let result = unsafe {
+        yara_sys::yr_object_function_create(
+            identifier.as_ptr(),
+            arguments_fmt.as_ptr(),
+            return_fmt.as_ptr(),
+            func,
+            parent,
+            &mut function,
+        )
+    };
+
+    yara_sys::Error::from_code(result)
+        .map(|()| function)
+        .map_err(Into::into)
+}
+
+/// Set the integer value of `field` in `object`, or of `object` itself if `field` is `None`.
+///
+/// # Safety
+///
+/// `object` must be a valid object.
+pub unsafe fn object_set_integer(
+    object: *mut YR_OBJECT,
+    field: Option<&CStr>,
+    value: i64,
+) -> Result<(), YaraError> {
+    let result = match field {
+        Some(field) => yara_sys::yr_object_set_integer(
+            value,
+            object,
+            FIELD_FORMAT.as_ptr().cast(),
+            field.as_ptr(),
+        ),
+        None => yara_sys::yr_object_set_integer(value, object, ptr::null()),
+    };
+
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
+
+/// Set the float value of `field` in `object`, or of `object` itself if `field` is `None`.
+///
+/// # Safety
+///
+/// `object` must be a valid object.
+pub unsafe fn object_set_float(
+    object: *mut YR_OBJECT,
+    field: Option<&CStr>,
+    value: f64,
+) -> Result<(), YaraError> {
+    let result = match field {
+        Some(field) => yara_sys::yr_object_set_float(
+            value,
+            object,
+            FIELD_FORMAT.as_ptr().cast(),
+            field.as_ptr(),
+        ),
+        None => yara_sys::yr_object_set_float(value, object, ptr::null()),
+    };
+
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
+
+/// Set the string value of `field` in `object`, or of `object` itself if `field` is `None`.
+///
+/// The string is copied by libyara. `None` sets the string as undefined.
+///
+/// # Safety
+///
+/// `object` must be a valid object.
+pub unsafe fn object_set_string(
+    object: *mut YR_OBJECT,
+    field: Option<&CStr>,
+    value: Option<&[u8]>,
+) -> Result<(), YaraError> {
+    let (ptr, len) = match value {
+        Some(value) => (value.as_ptr() as *const c_char, value.len()),
+        None => (ptr::null(), 0),
+    };
+    let result = match field {
+        Some(field) => yara_sys::yr_object_set_string(
+            ptr,
+            len as _,
+            object,
+            FIELD_FORMAT.as_ptr().cast(),
+            field.as_ptr(),
+        ),
+        None => yara_sys::yr_object_set_string(ptr, len as _, object, ptr::null()),
+    };
+
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
@@ -2,6 +2,9 @@ use std::collections::HashMap;
 use std::ffi::CStr;
 use std::fmt::Debug;
 
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
 /// A value from a module.
 pub struct YrObject<'a>(&'a yara_sys::YR_OBJECT);
 
@@ -17,7 +20,7 @@ impl<'a> From<&'a yara_sys::YR_OBJECT> for YrObject<'a> {
     }
 }
 
-impl YrObject<'_> {
+impl<'a> YrObject<'a> {
     /// Get the identifier of the object.
     ///
     /// This is not always set, depending on the object.
@@ -37,7 +40,7 @@ impl YrObject<'_> {
     }
 
     /// Get the value of the object.
-    pub fn value(&self) -> YrObjectValue<'_> {
+    pub fn value(&self) -> YrObjectValue<'a> {
         unsafe {
             match self.0.type_ as u32 {
                 yara_sys::OBJECT_TYPE_INTEGER => {
@@ -105,40 +108,227 @@ impl YrObject<'_> {
                     )
                 }
                 yara_sys::OBJECT_TYPE_DICTIONARY => {
-                    let this: &yara_sys::YR_OBJECT_DICTIONARY = std::mem::transmute(self.0);
-                    if this.items.is_null() {
-                        return YrObjectValue::Dictionary(HashMap::new());
-                    }
+                    YrObjectValue::Dictionary(self.dictionary_items().into_iter().collect())
+                }
+                yara_sys::OBJECT_TYPE_FUNCTION => YrObjectValue::Function,
+                _ => YrObjectValue::Undefined,
+            }
+        }
+    }
+}
 
-                    let objects = std::slice::from_raw_parts(
-                        (*this.items).objects.as_ptr(),
-                        (*this.items).used as usize,
-                    );
+impl<'a> YrObject<'a> {
+    /// Get a descendant of the object from a path, using the syntax of the Yara conditions.
+    ///
+    /// The path can start with the identifier of the object itself, e.g. the module name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use yara::{CallbackMsg, CallbackReturn, Compiler};
+    /// let rules = Compiler::new()?
+    ///     .add_rules_str("import \"pe\" rule is_dll { condition: pe.is_dll() }")?
+    ///     .compile_rules()?;
+    /// rules.scan_mem_callback(b"", 10, |message| {
+    ///     if let CallbackMsg::ModuleImported(pe) = message {
+    ///         assert_eq!(pe.get("pe.IMPORT_STANDARD").and_then(|o| o.as_i64()), Some(1));
+    ///         assert!(pe.get("sections[2].name").is_none());
+    ///         assert!(pe.get("version_info[\"CompanyName\"]").is_none());
+    ///     }
+    ///     CallbackReturn::Continue
+    /// })?;
+    /// # Ok::<(), yara::Error>(())
+    /// ```
+    pub fn get(&self, path: &str) -> Option<YrObject<'a>> {
+        let mut elements = parse_path(path)?;
+        if let Some(PathElement::Member(first)) = elements.first() {
+            if self.identifier() == Some(first.as_bytes()) && self.member(first).is_none() {
+                elements.remove(0);
+            }
+        }
 
-                    YrObjectValue::Dictionary(
-                        objects
-                            .iter()
-                            .filter_map(|v| {
-                                if v.key.is_null() || v.obj.is_null() {
-                                    return None;
-                                }
+        let mut object = YrObject(self.0);
+        for element in elements {
+            object = match element {
+                PathElement::Member(identifier) => object.member(identifier)?,
+                PathElement::Index(index) => object.iter_array()?.nth(index)??,
+                PathElement::Key(key) => object
+                    .iter_dict()?
+                    .find(|(k, _)| *k == key.as_bytes())
+                    .map(|(_, value)| value)?,
+            };
+        }
+        Some(object)
+    }
 
-                                let key = std::slice::from_raw_parts(
-                                    (*v.key).c_string.as_ptr().cast(),
-                                    (*v.key).length as usize,
-                                );
-                                Some((key, YrObject::from(&*v.obj)))
-                            })
-                            .collect(),
-                    )
+    /// The value of an integer object, if defined.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.value() {
+            YrObjectValue::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The value of a float object, if defined.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.value() {
+            YrObjectValue::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    /// The value of a string object, if defined.
+    pub fn as_bytes(&self) -> Option<&'a [u8]> {
+        match self.value() {
+            YrObjectValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the items of an array object. Missing items are `None`.
+    pub fn iter_array(&self) -> Option<impl Iterator<Item = Option<YrObject<'a>>>> {
+        match self.value() {
+            YrObjectValue::Array(items) => Some(items.into_iter()),
+            _ => None,
+        }
+    }
+
+    /// Iterate over the keys and values of a dictionary object, in insertion order.
+    pub fn iter_dict(&self) -> Option<impl Iterator<Item = (&'a [u8], YrObject<'a>)>> {
+        if self.0.type_ as u32 == yara_sys::OBJECT_TYPE_DICTIONARY {
+            // Safety: the object is a dictionary.
+            Some(unsafe { self.dictionary_items() }.into_iter())
+        } else {
+            None
+        }
+    }
+
+    fn member(&self, identifier: &str) -> Option<YrObject<'a>> {
+        match self.value() {
+            YrObjectValue::Structure(members) => members
+                .into_iter()
+                .find(|member| member.identifier() == Some(identifier.as_bytes())),
+            _ => None,
+        }
+    }
+
+    /// Items of a dictionary object, in insertion order.
+    ///
+    /// Must only be called on objects of type `OBJECT_TYPE_DICTIONARY`.
+    unsafe fn dictionary_items(&self) -> Vec<(&'a [u8], YrObject<'a>)> {
+        let this: &yara_sys::YR_OBJECT_DICTIONARY = std::mem::transmute(self.0);
+        if this.items.is_null() {
+            return Vec::new();
+        }
+
+        let objects =
+            std::slice::from_raw_parts((*this.items).objects.as_ptr(), (*this.items).used as usize);
+
+        objects
+            .iter()
+            .filter_map(|v| {
+                if v.key.is_null() || v.obj.is_null() {
+                    return None;
                 }
-                yara_sys::OBJECT_TYPE_FUNCTION => YrObjectValue::Function,
-                _ => YrObjectValue::Undefined,
+
+                let key = std::slice::from_raw_parts(
+                    (*v.key).c_string.as_ptr().cast(),
+                    (*v.key).length as usize,
+                );
+                Some((key, YrObject::from(&*v.obj)))
+            })
+            .collect()
+    }
+}
+
+/// Serialize the object like `yara -D` prints it.
+///
+/// Structures are serialized as maps of their members (without the functions),
+/// arrays as sequences, dictionaries as maps in insertion order, and undefined
+/// values as `None`. Strings are serialized as strings when they are valid UTF-8, and as
+/// bytes otherwise.
+#[cfg(feature = "serde")]
+impl Serialize for YrObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value() {
+            YrObjectValue::Integer(i) => serializer.serialize_i64(i),
+            YrObjectValue::Float(f) => serializer.serialize_f64(f),
+            YrObjectValue::String(s) => match std::str::from_utf8(s) {
+                Ok(s) => serializer.serialize_str(s),
+                Err(_) => serializer.serialize_bytes(s),
+            },
+            YrObjectValue::Array(items) => serializer.collect_seq(items),
+            YrObjectValue::Dictionary(_) => serializer.collect_map(
+                self.iter_dict()
+                    .into_iter()
+                    .flatten()
+                    .map(|(key, value)| (String::from_utf8_lossy(key), value)),
+            ),
+            YrObjectValue::Structure(members) => {
+                let members: Vec<_> = members
+                    .iter()
+                    .filter(|member| !matches!(member.value(), YrObjectValue::Function))
+                    .collect();
+                let mut map = serializer.serialize_map(Some(members.len()))?;
+                for member in members {
+                    let identifier = member.identifier().unwrap_or_default();
+                    map.serialize_entry(&String::from_utf8_lossy(identifier), member)?;
+                }
+                map.end()
             }
+            YrObjectValue::Function | YrObjectValue::Undefined => serializer.serialize_none(),
         }
     }
 }
 
+enum PathElement<'p> {
+    Member(&'p str),
+    Index(usize),
+    Key(String),
+}
+
+/// Parse a path like `sections[2].name` or `version_info["CompanyName"]`.
+fn parse_path(path: &str) -> Option<Vec<PathElement<'_>>> {
+    let mut elements = Vec::new();
+    let mut rest = path;
+
+    loop {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return None;
+        }
+        elements.push(PathElement::Member(&rest[..end]));
+        rest = &rest[end..];
+
+        while let Some(subscript) = rest.strip_prefix('[') {
+            if let Some(quoted) = subscript.strip_prefix('"') {
+                let mut key = String::new();
+                let mut chars = quoted.char_indices();
+                let end = loop {
+                    match chars.next()? {
+                        (i, '"') => break i,
+                        (_, '\\') => key.push(chars.next()?.1),
+                        (_, c) => key.push(c),
+                    }
+                };
+                elements.push(PathElement::Key(key));
+                rest = quoted[end + 1..].strip_prefix(']')?;
+            } else {
+                let (index, after) = subscript.split_once(']')?;
+                elements.push(PathElement::Index(index.trim().parse().ok()?));
+                rest = after;
+            }
+        }
+
+        if rest.is_empty() {
+            return Some(elements);
+        }
+        rest = rest.strip_prefix('.')?;
+    }
+}
+
 /// A value stored in a [`YrObject`].
 #[derive(Debug)]
 pub enum YrObjectValue<'a> {
@@ -151,3 +341,31 @@ pub enum YrObjectValue<'a> {
     Function,
     Undefined,
 }
+
+#[cfg(test)]
+mod test {
+    use super::{parse_path, PathElement};
+
+    #[test]
+    fn path() {
+        let elements = parse_path(r#"pe.version_info["Company\"Name"].sections[2].name"#).unwrap();
+        assert!(matches!(
+            elements.as_slice(),
+            [
+                PathElement::Member("pe"),
+                PathElement::Member("version_info"),
+                PathElement::Key(key),
+                PathElement::Member("sections"),
+                PathElement::Index(2),
+                PathElement::Member("name"),
+            ] if key == "Company\"Name"
+        ));
+
+        assert!(parse_path("").is_none());
+        assert!(parse_path("pe.").is_none());
+        assert!(parse_path("pe..a").is_none());
+        assert!(parse_path("sections[a]").is_none());
+        assert!(parse_path("sections[0").is_none());
+        assert!(parse_path("a[\"b]").is_none());
+    }
+}
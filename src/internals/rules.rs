@@ -1,13 +1,16 @@
 use std::ffi::{CStr, CString};
 use std::io::{Read, Write};
 use std::marker;
+use std::mem::MaybeUninit;
 use std::os::raw::c_char;
 use std::ptr;
 
 use crate::errors::*;
 use crate::internals::meta::MetadataIterator;
-use crate::internals::string::YrStringIterator;
-use crate::rules::RulesetRule;
+use crate::internals::string::{is_chain_part, YrStringIterator};
+use crate::rules::{ExternalVariable, ExternalVariableValue, RulesStats, RulesetRule};
+use crate::string::RuleString;
+use crate::ExternalVariableType;
 use crate::{Metadata, Rule, YrString};
 
 pub fn rules_destroy(rules: *mut yara_sys::YR_RULES) {
@@ -45,6 +48,96 @@ pub fn get_rules<'a>(ruleset: *mut yara_sys::YR_RULES) -> Vec<RulesetRule<'a>> {
     result
 }
 
+pub fn rules_get_stats(rules: *mut yara_sys::YR_RULES) -> Result<RulesStats, YaraError> {
+    let mut stats = MaybeUninit::<yara_sys::YR_RULES_STATS>::uninit();
+    let result = unsafe { yara_sys::yr_rules_get_stats(rules, stats.as_mut_ptr()) };
+    yara_sys::Error::from_code(result)
+        .map(|()| RulesStats::from(unsafe { &stats.assume_init() }))
+        .map_err(|e| e.into())
+}
+
+pub fn get_external_variables<'a>(rules: *const yara_sys::YR_RULES) -> Vec<ExternalVariable<'a>> {
+    ExternalVariableIterator::from(unsafe { &*rules })
+        .map(ExternalVariable::from)
+        .collect()
+}
+
+/// Check that an external variable is declared in the rules, with the given type if known.
+pub fn check_external_variable(
+    rules: *const yara_sys::YR_RULES,
+    identifier: &str,
+    variable_type: Option<ExternalVariableType>,
+) -> Result<(), VariableError> {
+    let variable = ExternalVariableIterator::from(unsafe { &*rules })
+        .find(|v| unsafe { CStr::from_ptr(v.get_identifier()) }.to_bytes() == identifier.as_bytes())
+        .ok_or_else(|| VariableError::Undefined(identifier.to_string()))?;
+    let expected = ExternalVariableType::from_code(variable.type_).unwrap();
+
+    match variable_type {
+        Some(actual) if actual != expected => Err(VariableError::TypeMismatch {
+            identifier: identifier.to_string(),
+            expected,
+            actual,
+        }),
+        _ => Ok(()),
+    }
+}
+
+pub fn rules_define_integer_variable(
+    rules: *mut yara_sys::YR_RULES,
+    identifier: &str,
+    value: i64,
+) -> Result<(), YaraError> {
+    let identifier = CString::new(identifier).unwrap();
+    let result =
+        unsafe { yara_sys::yr_rules_define_integer_variable(rules, identifier.as_ptr(), value) };
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
+
+pub fn rules_define_boolean_variable(
+    rules: *mut yara_sys::YR_RULES,
+    identifier: &str,
+    value: bool,
+) -> Result<(), YaraError> {
+    let identifier = CString::new(identifier).unwrap();
+    let value = i32::from(value);
+    let result =
+        unsafe { yara_sys::yr_rules_define_boolean_variable(rules, identifier.as_ptr(), value) };
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
+
+pub fn rules_define_float_variable(
+    rules: *mut yara_sys::YR_RULES,
+    identifier: &str,
+    value: f64,
+) -> Result<(), YaraError> {
+    let identifier = CString::new(identifier).unwrap();
+    let result =
+        unsafe { yara_sys::yr_rules_define_float_variable(rules, identifier.as_ptr(), value) };
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
+
+pub fn rules_define_str_variable(
+    rules: *mut yara_sys::YR_RULES,
+    identifier: &str,
+    value: &str,
+) -> Result<(), YaraError> {
+    let value = CString::new(value).unwrap();
+    rules_define_cstr_variable(rules, identifier, &value)
+}
+
+pub fn rules_define_cstr_variable(
+    rules: *mut yara_sys::YR_RULES,
+    identifier: &str,
+    value: &CStr,
+) -> Result<(), YaraError> {
+    let identifier = CString::new(identifier).unwrap();
+    let result = unsafe {
+        yara_sys::yr_rules_define_string_variable(rules, identifier.as_ptr(), value.as_ptr())
+    };
+    yara_sys::Error::from_code(result).map_err(Into::into)
+}
+
 // TODO Check if non mut
 pub fn rules_save(rules: *mut yara_sys::YR_RULES, filename: &str) -> Result<(), YaraError> {
     let filename = CString::new(filename).unwrap();
@@ -134,6 +227,84 @@ impl<'a> From<(&'a yara_sys::YR_SCAN_CONTEXT, &'a yara_sys::YR_RULE)> for Rule<'
     }
 }
 
+impl From<&yara_sys::YR_RULES_STATS> for RulesStats {
+    fn from(stats: &yara_sys::YR_RULES_STATS) -> Self {
+        RulesStats {
+            num_rules: stats.num_rules,
+            num_strings: stats.num_strings,
+            ac_matches: stats.ac_matches,
+            ac_root_match_list_length: stats.ac_root_match_list_length,
+            ac_average_match_list_length: stats.ac_average_match_list_length,
+            top_ac_match_list_lengths: stats.top_ac_match_list_lengths.to_vec(),
+            ac_match_list_length_pctls: stats.ac_match_list_length_pctls.to_vec(),
+            ac_tables_size: stats.ac_tables_size,
+        }
+    }
+}
+
+impl<'a> From<&'a yara_sys::YR_EXTERNAL_VARIABLE> for ExternalVariable<'a> {
+    fn from(variable: &'a yara_sys::YR_EXTERNAL_VARIABLE) -> Self {
+        let identifier = unsafe { CStr::from_ptr(variable.get_identifier()) }
+            .to_str()
+            .unwrap();
+        let value = match ExternalVariableType::from_code(variable.type_).unwrap() {
+            ExternalVariableType::Integer => {
+                ExternalVariableValue::Integer(unsafe { variable.value.i })
+            }
+            ExternalVariableType::Float => {
+                ExternalVariableValue::Float(unsafe { variable.value.f })
+            }
+            ExternalVariableType::Boolean => {
+                ExternalVariableValue::Boolean(unsafe { variable.value.i } != 0)
+            }
+            ExternalVariableType::String => {
+                ExternalVariableValue::String(unsafe { CStr::from_ptr(variable.value.s) })
+            }
+        };
+
+        ExternalVariable { identifier, value }
+    }
+}
+
+/// Iterate over YR_EXTERNAL_VARIABLE in a YR_RULES.
+///
+/// # Implementation notes
+///
+/// The table is terminated by a variable of type `EXTERNAL_VARIABLE_TYPE_NULL`.
+/// See `external_variable_is_null` in Yara.
+struct ExternalVariableIterator<'a> {
+    head: *const yara_sys::YR_EXTERNAL_VARIABLE,
+    _marker: marker::PhantomData<&'a yara_sys::YR_EXTERNAL_VARIABLE>,
+}
+
+impl<'a> From<&'a yara_sys::YR_RULES> for ExternalVariableIterator<'a> {
+    fn from(rules: &'a yara_sys::YR_RULES) -> Self {
+        ExternalVariableIterator {
+            head: rules.get_ext_vars_table(),
+            _marker: marker::PhantomData,
+        }
+    }
+}
+
+impl<'a> Iterator for ExternalVariableIterator<'a> {
+    type Item = &'a yara_sys::YR_EXTERNAL_VARIABLE;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.head.is_null() {
+            return None;
+        }
+
+        let variable = unsafe { &*self.head };
+        if variable.type_ as u32 == yara_sys::EXTERNAL_VARIABLE_TYPE_NULL {
+            self.head = std::ptr::null();
+            None
+        } else {
+            self.head = unsafe { self.head.add(1) };
+            Some(variable)
+        }
+    }
+}
+
 /// Iterate over YR_RULE in a YR_RULES.
 ///
 /// # Implementation notes
@@ -168,12 +339,17 @@ impl<'a> Iterator for RuleIterator<'a> {
             self.head = std::ptr::null();
         } else {
             let rule_data = Rule::from(unsafe { &*self.head });
+            let strings = YrStringIterator::from(unsafe { &*self.head })
+                .filter(|s| !is_chain_part(s))
+                .map(RuleString::from)
+                .collect();
             result = Some(RulesetRule {
                 inner: self.head as *mut yara_sys::YR_RULE,
                 identifier: rule_data.identifier,
                 namespace: rule_data.namespace,
                 tags: rule_data.tags,
                 metadatas: rule_data.metadatas,
+                strings,
             });
             self.head = unsafe { self.head.offset(1) };
         }
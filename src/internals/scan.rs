@@ -7,6 +7,8 @@ use std::os::unix::io::AsRawFd;
 use std::os::windows::io::AsRawHandle;
 
 use crate::internals::*;
+#[cfg(feature = "profiling")]
+use crate::profiling::RuleProfilingInfo;
 use crate::{Rule, YrString};
 
 #[derive(Debug)]
@@ -127,43 +129,6 @@ pub fn scanner_scan_mem<'a>(
         .map(|_| ())
 }
 
-#[cfg(unix)]
-pub fn rules_scan_file<'a, F: AsRawFd>(
-    rules: *mut yara_sys::YR_RULES,
-    file: &F,
-    timeout: i32,
-    flags: i32,
-    mut callback: impl FnMut(CallbackMsg<'a>) -> CallbackReturn,
-) -> Result<(), YaraError> {
-    let fd = file.as_raw_fd();
-    let (user_data, scan_callback) = get_scan_callback(&mut callback);
-
-    let result =
-        unsafe { yara_sys::yr_rules_scan_fd(rules, fd, flags, scan_callback, user_data, timeout) };
-    yara_sys::Error::from_code(result)
-        .map_err(|e| e.into())
-        .map(|_| ())
-}
-
-#[cfg(windows)]
-pub fn rules_scan_file<'a, F: AsRawHandle>(
-    rules: *mut yara_sys::YR_RULES,
-    file: &F,
-    timeout: i32,
-    flags: i32,
-    mut callback: impl FnMut(CallbackMsg<'a>) -> CallbackReturn,
-) -> Result<(), YaraError> {
-    let handle = file.as_raw_handle();
-    let (user_data, scan_callback) = get_scan_callback(&mut callback);
-
-    let result = unsafe {
-        yara_sys::yr_rules_scan_fd(rules, handle, flags, scan_callback, user_data, timeout)
-    };
-    yara_sys::Error::from_code(result)
-        .map_err(|e| e.into())
-        .map(|_| ())
-}
-
 #[cfg(unix)]
 /// Scan a file with the provided YR_SCANNER and its defined external vars.
 ///
@@ -281,6 +246,29 @@ fn scanner_scan_mem_blocks_inner<'a>(
         .map(|_| ())
 }
 
+/// The rule and string that Yara was matching when the last scan failed, if any.
+pub fn scanner_last_error_rule(scanner: *mut yara_sys::YR_SCANNER) -> Option<ErrorRule> {
+    let rule = unsafe { yara_sys::yr_scanner_last_error_rule(scanner) };
+    if rule.is_null() {
+        return None;
+    }
+    let rule = Rule::from(unsafe { &*rule });
+
+    let string = unsafe { yara_sys::yr_scanner_last_error_string(scanner) };
+    let string = if string.is_null() {
+        None
+    } else {
+        let identifier = unsafe { CStr::from_ptr((*string).get_identifier()) };
+        Some(identifier.to_string_lossy().into_owned())
+    };
+
+    Some(ErrorRule {
+        namespace: rule.namespace.to_string(),
+        identifier: rule.identifier.to_string(),
+        string,
+    })
+}
+
 pub fn get_scan_callback<'a, F>(closure: &mut F) -> (*mut c_void, yara_sys::YR_CALLBACK_FUNC)
 where
     F: FnMut(CallbackMsg<'a>) -> CallbackReturn,
@@ -305,6 +293,44 @@ where
     callback(message).to_yara()
 }
 
+/// Get the profiling information of the rules, sorted by decreasing cost.
+#[cfg(feature = "profiling")]
+pub fn scanner_get_profiling_info<'a>(
+    scanner: *mut yara_sys::YR_SCANNER,
+) -> Result<Vec<RuleProfilingInfo<'a>>, YaraError> {
+    let info = unsafe { yara_sys::yr_scanner_get_profiling_info(scanner) };
+    if info.is_null() {
+        return Err(yara_sys::Error::InsufficientMemory.into());
+    }
+
+    let mut result = Vec::new();
+    // The array is terminated by an entry with a null rule.
+    let mut current = info;
+    unsafe {
+        while !(*current).rule.is_null() {
+            let rule = Rule::from(&*(*current).rule);
+            result.push(RuleProfilingInfo {
+                identifier: rule.identifier,
+                namespace: rule.namespace,
+                cost: (*current).cost,
+            });
+            current = current.add(1);
+        }
+        yara_sys::yr_free(info as *mut c_void);
+    }
+
+    Ok(result)
+}
+
+/// Resetting the profiling info modifies the Scanner with no locks preventing data races,
+/// so it should only be called from a &mut Scanner.
+#[cfg(feature = "profiling")]
+pub fn scanner_reset_profiling_info(scanner: *mut yara_sys::YR_SCANNER) {
+    unsafe {
+        yara_sys::yr_scanner_reset_profiling_info(scanner);
+    }
+}
+
 /// Setting the flags modifies the Scanner with no locks preventing data races,
 /// so it should only be called from a &mut Scanner.
 pub fn scanner_set_flags(scanner: *mut yara_sys::YR_SCANNER, flags: i32) {
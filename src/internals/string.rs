@@ -4,6 +4,7 @@ use std::marker;
 use yara_sys::{YR_SCAN_CONTEXT, YR_STRING};
 
 use crate::internals::matches::MatchIterator;
+use crate::string::{RuleString, RuleStringKind, RuleStringModifiers};
 use crate::Match;
 use crate::YrString;
 
@@ -60,3 +61,57 @@ impl<'a> From<(&'a YR_SCAN_CONTEXT, &'a YR_STRING)> for YrString<'a> {
         }
     }
 }
+
+impl<'a> From<&'a YR_STRING> for RuleString<'a> {
+    fn from(string: &'a YR_STRING) -> Self {
+        let identifier = unsafe { CStr::from_ptr(string.get_identifier()) }
+            .to_str()
+            .unwrap();
+        let has_flag = |flag: u32| string.flags & flag != 0;
+
+        // Hex strings with jumps or alternatives are compiled as regular expressions,
+        // but keep the hexadecimal flag.
+        let kind = if has_flag(yara_sys::STRING_FLAGS_HEXADECIMAL) {
+            RuleStringKind::Hex
+        } else if has_flag(yara_sys::STRING_FLAGS_REGEXP) {
+            RuleStringKind::Regex
+        } else {
+            RuleStringKind::Literal
+        };
+        let modifiers = RuleStringModifiers {
+            nocase: has_flag(yara_sys::STRING_FLAGS_NO_CASE),
+            wide: has_flag(yara_sys::STRING_FLAGS_WIDE),
+            ascii: has_flag(yara_sys::STRING_FLAGS_ASCII),
+            xor: has_flag(yara_sys::STRING_FLAGS_XOR),
+            base64: has_flag(yara_sys::STRING_FLAGS_BASE64),
+            base64wide: has_flag(yara_sys::STRING_FLAGS_BASE64_WIDE),
+            fullword: has_flag(yara_sys::STRING_FLAGS_FULL_WORD),
+            private: has_flag(yara_sys::STRING_FLAGS_PRIVATE),
+        };
+        // The flag is cleared when the string is used without `at`, but the offset
+        // is only set once the string is used with it.
+        let fixed_offset = if has_flag(yara_sys::STRING_FLAGS_FIXED_OFFSET)
+            && string.fixed_offset != yara_sys::YR_UNDEFINED
+        {
+            Some(string.fixed_offset)
+        } else {
+            None
+        };
+
+        RuleString {
+            identifier,
+            kind,
+            modifiers,
+            fixed_offset,
+        }
+    }
+}
+
+/// Whether the string is a part of a chained string other than its tail.
+///
+/// Hex strings with large jumps are split into several chained strings, all
+/// sharing the same identifier.
+pub fn is_chain_part(string: &YR_STRING) -> bool {
+    string.flags & yara_sys::STRING_FLAGS_CHAIN_PART != 0
+        && string.flags & yara_sys::STRING_FLAGS_CHAIN_TAIL == 0
+}
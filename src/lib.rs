@@ -46,30 +46,61 @@
 //! [polydet]: https://github.com/Polydet/polydet/
 
 use internals::configuration;
-pub use internals::{YrObject, YrObjectValue};
+pub use internals::{YrModuleImport, YrObject, YrObjectValue};
 
+#[cfg(feature = "tokio")]
+pub use crate::async_rules::AsyncRules;
+pub use crate::cancellation::CancellationToken;
 pub use crate::compiler::{Compiler, CompilerVariableValue};
 pub use crate::errors::*;
 pub use crate::flags::ScanFlags;
 use crate::initialize::InitializationToken;
 pub use crate::matches::Match;
-pub use crate::rules::{Metadata, MetadataValue, Rule, Rules, RulesetRule};
+pub use crate::module_data::{ModuleData, OwnedYrObjectValue};
+pub use crate::options::ScanOptions;
+pub use crate::outcome::ScanOutcome;
+#[cfg(feature = "profiling")]
+pub use crate::profiling::RuleProfilingInfo;
+pub use crate::reader::ReaderMemoryBlockIterator;
+pub use crate::rules::{
+    ExternalVariable, ExternalVariableValue, Metadata, MetadataValue, OwnedMetadata,
+    OwnedMetadataValue, OwnedRule, Rule, Rules, RulesStats, RulesetRule,
+};
 pub use crate::scanner::Scanner;
-pub use crate::string::YrString;
+pub use crate::selector::{MetadataComparison, MetadataPredicate, RuleSelector, RulesEnablement};
+pub use crate::string::{OwnedYrString, RuleString, RuleStringKind, RuleStringModifiers, YrString};
 pub use internals::{
     CallbackMsg, CallbackReturn, MemoryBlock, MemoryBlockIterator, MemoryBlockIteratorSized,
 };
 
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "tokio")]
+mod async_rules;
+mod cancellation;
 mod compiler;
+pub mod dump;
+mod glob;
 mod initialize;
 mod internals;
 mod matches;
+pub mod module;
+mod module_data;
+mod options;
+mod outcome;
+#[cfg(target_os = "linux")]
+pub mod process;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod reader;
 mod rules;
 mod scanner;
+mod selector;
 mod string;
 
 pub mod errors;
 mod flags;
+pub mod walk;
 
 /// Yara initialization token.
 ///
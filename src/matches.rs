@@ -2,7 +2,7 @@
 use serde::{Deserialize, Serialize};
 
 /// A match within a scan.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Match {
     // base offset of the memory block in which the match occurred.
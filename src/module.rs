@@ -0,0 +1,722 @@
+//! Yara modules written in Rust.
+//!
+//! A module is a type implementing [`Module`], exported to libyara with
+//! [`declare_module!`](crate::declare_module):
+//!
+//! ```no_run
+//! use yara::module::{Declarations, FunctionContext, Module, ModuleObject, ReturnValue, ScanData};
+//! use yara::YaraError;
+//!
+//! struct OurCorp;
+//!
+//! impl Module for OurCorp {
+//!     fn declarations(module: &mut Declarations) -> Result<(), YaraError> {
+//!         module.integer("first_byte")?;
+//!         module.structure("config", |config| config.string("name"))?;
+//!         module.function("add", "ii", "i", |ctx: &FunctionContext| {
+//!             match (ctx.integer(0), ctx.integer(1)) {
+//!                 (Some(a), Some(b)) => ReturnValue::Integer(a.wrapping_add(b)),
+//!                 _ => ReturnValue::Undefined,
+//!             }
+//!         })
+//!     }
+//!
+//!     fn load(
+//!         module: &mut ModuleObject,
+//!         scan_data: &mut ScanData,
+//!         module_data: Option<&[u8]>,
+//!     ) -> Result<(), YaraError> {
+//!         if let Some(block) = scan_data.first_block() {
+//!             if let Some(byte) = block.data().first() {
+//!                 module.set_integer("first_byte", i64::from(*byte))?;
+//!             }
+//!         }
+//!         if let Some(name) = module_data {
+//!             module.set_string("config.name", name)?;
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! yara::declare_module!(ourcorp, OurCorp);
+//! ```
+//!
+//! libyara only knows the modules listed in its modules table, which is built with it.
+//! Modules written in Rust thus need the `vendored` feature, and their names must be listed,
+//! comma-separated, in the `YARA_RUST_MODULES` environment variable when building
+//! `yara-sys`, e.g. `YARA_RUST_MODULES=ourcorp`.
+//!
+//! Because libyara is linked as a static archive, [`declare_module!`](crate::declare_module)
+//! must be invoked in the crate of the final binary, or in a crate the binary references,
+//! for the linker to find the module functions.
+//!
+//! # Linking
+//!
+//! The modules table refers to the functions of each module listed in
+//! `YARA_RUST_MODULES`, so *every* binary linking this build of `yara-sys` must declare
+//! all of them, or it fails to link with undefined `<name>__declarations` symbols.
+//! This includes the tests, examples and doctests of the crates depending on `yara`,
+//! and the `yara-rs` binary. Set the variable only when building the binaries declaring
+//! the modules, for example `YARA_RUST_MODULES=ourcorp cargo build --bin scanner`.
+
+use std::any::Any;
+use std::ffi::{c_void, CStr};
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::sync::{Mutex, PoisonError};
+
+use yara_sys::{YR_MODULE_FUNC, YR_OBJECT, YR_OBJECT_FUNCTION, YR_SCAN_CONTEXT, YR_VALUE};
+
+use crate::errors::*;
+use crate::internals::module::*;
+use crate::internals::{MemoryBlock, YrObject};
+
+/// A Yara module written in Rust.
+///
+/// The functions are called by libyara during the scans, each time a rule imports the module.
+/// Panics are caught and reported as [`InternalFatalError`](YaraErrorKind::InternalFatalError).
+pub trait Module {
+    /// Declare the fields and functions of the module.
+    fn declarations(module: &mut Declarations) -> Result<(), YaraError>;
+
+    /// Set the fields of the module for the scanned data.
+    ///
+    /// `module_data` is the data set with
+    /// [`Scanner::set_module_data`](crate::Scanner::set_module_data), if any.
+    fn load(
+        module: &mut ModuleObject,
+        scan_data: &mut ScanData,
+        module_data: Option<&[u8]>,
+    ) -> Result<(), YaraError>;
+
+    /// Called at the end of the scan.
+    ///
+    /// The data set with [`ModuleObject::set_data`] is dropped right after.
+    fn unload(_module: &mut ModuleObject) -> Result<(), YaraError> {
+        Ok(())
+    }
+}
+
+/// Declarations of the fields of a module or a structure.
+pub struct Declarations<'a> {
+    object: *mut YR_OBJECT,
+    _marker: PhantomData<&'a mut YR_OBJECT>,
+}
+
+impl Declarations<'_> {
+    fn new(object: *mut YR_OBJECT) -> Self {
+        Declarations {
+            object,
+            _marker: PhantomData,
+        }
+    }
+
+    fn create(&mut self, type_: u32, name: &str) -> Result<*mut YR_OBJECT, YaraError> {
+        object_create(type_, &to_cstring(name)?, self.object)
+    }
+
+    fn create_collection(
+        &mut self,
+        collection_type: u32,
+        item_type: u32,
+        name: &str,
+    ) -> Result<*mut YR_OBJECT, YaraError> {
+        let name = to_cstring(name)?;
+        let collection = object_create(collection_type, &name, self.object)?;
+        object_create(item_type, &name, collection)
+    }
+
+    /// Declare an integer field.
+    pub fn integer(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create(yara_sys::OBJECT_TYPE_INTEGER, name).map(drop)
+    }
+
+    /// Declare a float field.
+    pub fn float(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create(yara_sys::OBJECT_TYPE_FLOAT, name).map(drop)
+    }
+
+    /// Declare a string field.
+    pub fn string(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create(yara_sys::OBJECT_TYPE_STRING, name).map(drop)
+    }
+
+    /// Declare a structure field, whose members are declared by `declarations`.
+    pub fn structure<F>(&mut self, name: &str, declarations: F) -> Result<(), YaraError>
+    where
+        F: FnOnce(&mut Declarations) -> Result<(), YaraError>,
+    {
+        let object = self.create(yara_sys::OBJECT_TYPE_STRUCTURE, name)?;
+        declarations(&mut Declarations::new(object))
+    }
+
+    /// Declare an array of integers.
+    pub fn integer_array(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create_collection(
+            yara_sys::OBJECT_TYPE_ARRAY,
+            yara_sys::OBJECT_TYPE_INTEGER,
+            name,
+        )
+        .map(drop)
+    }
+
+    /// Declare an array of floats.
+    pub fn float_array(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create_collection(
+            yara_sys::OBJECT_TYPE_ARRAY,
+            yara_sys::OBJECT_TYPE_FLOAT,
+            name,
+        )
+        .map(drop)
+    }
+
+    /// Declare an array of strings.
+    pub fn string_array(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create_collection(
+            yara_sys::OBJECT_TYPE_ARRAY,
+            yara_sys::OBJECT_TYPE_STRING,
+            name,
+        )
+        .map(drop)
+    }
+
+    /// Declare an array of structures, whose members are declared by `declarations`.
+    pub fn structure_array<F>(&mut self, name: &str, declarations: F) -> Result<(), YaraError>
+    where
+        F: FnOnce(&mut Declarations) -> Result<(), YaraError>,
+    {
+        let object = self.create_collection(
+            yara_sys::OBJECT_TYPE_ARRAY,
+            yara_sys::OBJECT_TYPE_STRUCTURE,
+            name,
+        )?;
+        declarations(&mut Declarations::new(object))
+    }
+
+    /// Declare a dictionary of integers.
+    pub fn integer_dictionary(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create_collection(
+            yara_sys::OBJECT_TYPE_DICTIONARY,
+            yara_sys::OBJECT_TYPE_INTEGER,
+            name,
+        )
+        .map(drop)
+    }
+
+    /// Declare a dictionary of floats.
+    pub fn float_dictionary(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create_collection(
+            yara_sys::OBJECT_TYPE_DICTIONARY,
+            yara_sys::OBJECT_TYPE_FLOAT,
+            name,
+        )
+        .map(drop)
+    }
+
+    /// Declare a dictionary of strings.
+    pub fn string_dictionary(&mut self, name: &str) -> Result<(), YaraError> {
+        self.create_collection(
+            yara_sys::OBJECT_TYPE_DICTIONARY,
+            yara_sys::OBJECT_TYPE_STRING,
+            name,
+        )
+        .map(drop)
+    }
+
+    /// Declare a dictionary of structures, whose members are declared by `declarations`.
+    pub fn structure_dictionary<F>(&mut self, name: &str, declarations: F) -> Result<(), YaraError>
+    where
+        F: FnOnce(&mut Declarations) -> Result<(), YaraError>,
+    {
+        let object = self.create_collection(
+            yara_sys::OBJECT_TYPE_DICTIONARY,
+            yara_sys::OBJECT_TYPE_STRUCTURE,
+            name,
+        )?;
+        declarations(&mut Declarations::new(object))
+    }
+
+    /// Declare a function.
+    ///
+    /// `arguments` is the format of the arguments, one character per argument: `i` for an
+    /// integer, `f` for a float, `s` for a string and `r` for a regular expression.
+    /// `return_type` is `i`, `f` or `s`.
+    ///
+    /// A function can be overloaded by declaring it several times with the same name and
+    /// different arguments, but each overload must use its own `function` type: using the
+    /// same type with different arguments fails.
+    ///
+    /// `function` must not capture anything, i.e. it must be a function item or a closure
+    /// without captures, otherwise the module does not build.
+    pub fn function<F>(
+        &mut self,
+        name: &str,
+        arguments: &str,
+        return_type: &str,
+        _function: F,
+    ) -> Result<(), YaraError>
+    where
+        F: Fn(&FunctionContext) -> ReturnValue + Copy + 'static,
+    {
+        let code: YR_MODULE_FUNC = Some(function_trampoline::<F>);
+        let object = object_function_create(
+            &to_cstring(name)?,
+            static_arguments(arguments)?,
+            &to_cstring(return_type)?,
+            code,
+            self.object,
+        )?;
+        // Safety: object_function_create returns a function object.
+        let prototypes = unsafe { &(*object.cast::<YR_OBJECT_FUNCTION>()).prototypes };
+        if prototypes
+            .iter()
+            .filter(|p| same_code(p.code, code))
+            .count()
+            > 1
+        {
+            return Err(YaraErrorKind::Unknown(yara_sys::ERROR_INVALID_ARGUMENT as i32).into());
+        }
+        Ok(())
+    }
+}
+
+/// The value returned by a module function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReturnValue {
+    Integer(i64),
+    Float(f64),
+    String(Vec<u8>),
+    Undefined,
+}
+
+/// The arguments and the scan context of a module function call.
+pub struct FunctionContext<'a> {
+    arguments: &'a [YR_VALUE],
+    arguments_fmt: &'a [u8],
+    context: *mut YR_SCAN_CONTEXT,
+    function: *mut YR_OBJECT_FUNCTION,
+}
+
+impl<'a> FunctionContext<'a> {
+    fn argument(&self, index: usize, format: u8) -> Option<&'a YR_VALUE> {
+        if self.arguments_fmt.get(index) == Some(&format) {
+            self.arguments.get(index)
+        } else {
+            None
+        }
+    }
+
+    /// Get the integer argument at `index`.
+    ///
+    /// Returns `None` if the argument does not exist or is not an integer.
+    pub fn integer(&self, index: usize) -> Option<i64> {
+        // Safety: the format says the argument is an integer.
+        self.argument(index, b'i').map(|value| unsafe { value.i })
+    }
+
+    /// Get the float argument at `index`.
+    ///
+    /// Returns `None` if the argument does not exist or is not a float.
+    pub fn float(&self, index: usize) -> Option<f64> {
+        // Safety: the format says the argument is a float.
+        self.argument(index, b'f').map(|value| unsafe { value.d })
+    }
+
+    /// Get the string argument at `index`.
+    ///
+    /// Returns `None` if the argument does not exist or is not a string.
+    pub fn string(&self, index: usize) -> Option<&'a [u8]> {
+        self.argument(index, b's').map(|value| unsafe {
+            // Safety: the format says the argument is a sized string, valid during the call.
+            let string = value.ss;
+            std::slice::from_raw_parts(
+                (*string).c_string.as_ptr().cast(),
+                (*string).length as usize,
+            )
+        })
+    }
+
+    fn module_object(&self) -> *mut YR_OBJECT {
+        // Safety: the function is part of the module object tree, valid during the call.
+        unsafe {
+            let mut object = self.function as *mut YR_OBJECT;
+            while !(*object).parent.is_null() {
+                object = (*object).parent;
+            }
+            object
+        }
+    }
+
+    /// Get the object of the module the function belongs to.
+    pub fn module(&self) -> YrObject<'a> {
+        // Safety: the module object is valid during the call.
+        YrObject::from(unsafe { &*self.module_object() })
+    }
+
+    /// Get the data set with [`ModuleObject::set_data`] on the module object.
+    pub fn data<T: Any>(&self) -> Option<&'a T> {
+        // Safety: the data of the module object is only set by ModuleObject::set_data.
+        unsafe { object_data(self.module_object()) }
+    }
+
+    /// Get the scanned data.
+    pub fn scan_data(&self) -> ScanData<'a> {
+        ScanData::new(self.context)
+    }
+}
+
+/// The object of a module being loaded or unloaded.
+pub struct ModuleObject<'a> {
+    object: *mut YR_OBJECT,
+    _marker: PhantomData<&'a mut YR_OBJECT>,
+}
+
+impl ModuleObject<'_> {
+    fn new(object: *mut YR_OBJECT) -> Self {
+        ModuleObject {
+            object,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set an integer field.
+    ///
+    /// `field` is a path from the module, like `"count"`, `"sections[2].size"` or
+    /// `"headers[\"Host\"]"`. Array items and dictionary entries are created as needed.
+    pub fn set_integer(&mut self, field: &str, value: i64) -> Result<(), YaraError> {
+        // Safety: self.object is a valid module object.
+        unsafe { object_set_integer(self.object, Some(&to_cstring(field)?), value) }
+    }
+
+    /// Set a float field.
+    ///
+    /// See [`set_integer`](Self::set_integer) for the format of `field`.
+    pub fn set_float(&mut self, field: &str, value: f64) -> Result<(), YaraError> {
+        // Safety: self.object is a valid module object.
+        unsafe { object_set_float(self.object, Some(&to_cstring(field)?), value) }
+    }
+
+    /// Set a string field.
+    ///
+    /// See [`set_integer`](Self::set_integer) for the format of `field`.
+    pub fn set_string(&mut self, field: &str, value: impl AsRef<[u8]>) -> Result<(), YaraError> {
+        // Safety: self.object is a valid module object.
+        unsafe { object_set_string(self.object, Some(&to_cstring(field)?), Some(value.as_ref())) }
+    }
+
+    /// Attach data to the module, to be used by its functions with [`FunctionContext::data`].
+    ///
+    /// Replaces and drops the previous data. The data is dropped after [`Module::unload`].
+    pub fn set_data<T: Any + Send>(&mut self, data: T) {
+        self.drop_data();
+        let data: Box<Box<dyn Any + Send>> = Box::new(Box::new(data));
+        // Safety: self.object is a valid module object.
+        unsafe { (*self.object).data = Box::into_raw(data).cast() };
+    }
+
+    /// Get the data set with [`set_data`](Self::set_data).
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        // Safety: the data of the module object is only set by set_data.
+        unsafe { object_data(self.object) }
+    }
+
+    /// Get the object of the module, to read its fields.
+    pub fn object(&self) -> YrObject<'_> {
+        // Safety: self.object is a valid module object.
+        YrObject::from(unsafe { &*self.object })
+    }
+
+    fn drop_data(&mut self) {
+        // Safety: the data of the module object is only set by set_data.
+        unsafe {
+            let data = (*self.object).data as *mut Box<dyn Any + Send>;
+            if !data.is_null() {
+                drop(Box::from_raw(data));
+                (*self.object).data = ptr::null_mut();
+            }
+        }
+    }
+}
+
+/// The data being scanned, as memory blocks.
+///
+/// Files and buffers are a single block, process memory has one block per memory region.
+pub struct ScanData<'a> {
+    context: *mut YR_SCAN_CONTEXT,
+    _marker: PhantomData<&'a YR_SCAN_CONTEXT>,
+}
+
+impl ScanData<'_> {
+    fn new(context: *mut YR_SCAN_CONTEXT) -> Self {
+        ScanData {
+            context,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get the first memory block.
+    pub fn first_block(&mut self) -> Option<MemoryBlock<'_>> {
+        // Safety: the iterator of the scan context is valid during the scan.
+        unsafe {
+            let iterator = (*self.context).iterator;
+            let first = (*iterator).first?;
+            Self::to_memory_block(first(iterator))
+        }
+    }
+
+    /// Get the next memory block.
+    pub fn next_block(&mut self) -> Option<MemoryBlock<'_>> {
+        // Safety: the iterator of the scan context is valid during the scan.
+        unsafe {
+            let iterator = (*self.context).iterator;
+            let next = (*iterator).next?;
+            Self::to_memory_block(next(iterator))
+        }
+    }
+
+    /// Blocks whose data can not be fetched, like unreadable process memory, are empty.
+    unsafe fn to_memory_block<'b>(
+        block: *mut yara_sys::YR_MEMORY_BLOCK,
+    ) -> Option<MemoryBlock<'b>> {
+        if block.is_null() {
+            return None;
+        }
+        let data = match (*block).fetch_data {
+            Some(fetch_data) => fetch_data(block),
+            None => ptr::null(),
+        };
+        let data = if data.is_null() {
+            &[][..]
+        } else {
+            std::slice::from_raw_parts(data, (*block).size as usize)
+        };
+        Some(MemoryBlock::new((*block).base, data))
+    }
+}
+
+unsafe fn object_data<'a, T: Any>(object: *const YR_OBJECT) -> Option<&'a T> {
+    let data = (*object).data as *const Box<dyn Any + Send>;
+    if data.is_null() {
+        None
+    } else {
+        (**data).downcast_ref()
+    }
+}
+
+/// Arguments formats of the module functions.
+///
+/// libyara keeps the pointer to the arguments format of each function prototype, so the
+/// formats must live until the end of the program. Each distinct format is leaked once.
+static ARGUMENTS_FORMATS: Mutex<Vec<&'static CStr>> = Mutex::new(Vec::new());
+
+fn static_arguments(arguments: &str) -> Result<&'static CStr, YaraError> {
+    let arguments = to_cstring(arguments)?;
+    let mut formats = ARGUMENTS_FORMATS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(format) = formats
+        .iter()
+        .copied()
+        .find(|format| **format == *arguments)
+    {
+        return Ok(format);
+    }
+    let format: &'static CStr = Box::leak(arguments.into_boxed_c_str());
+    formats.push(format);
+    Ok(format)
+}
+
+fn same_code(a: YR_MODULE_FUNC, b: YR_MODULE_FUNC) -> bool {
+    a.map(|a| a as usize) == b.map(|b| b as usize)
+}
+
+/// Fails to build if a module function captures something, as the trampoline creates
+/// the function from nothing.
+struct ZeroSized<F>(PhantomData<F>);
+
+impl<F> ZeroSized<F> {
+    const ASSERT: () = assert!(
+        std::mem::size_of::<F>() == 0,
+        "module functions must not capture anything"
+    );
+}
+
+unsafe extern "C" fn function_trampoline<F>(
+    arguments: *mut YR_VALUE,
+    context: *mut YR_SCAN_CONTEXT,
+    function: *mut YR_OBJECT_FUNCTION,
+) -> c_int
+where
+    F: Fn(&FunctionContext) -> ReturnValue + Copy + 'static,
+{
+    let () = ZeroSized::<F>::ASSERT;
+
+    // libyara does not tell which prototype of an overloaded function is called, so
+    // find the one of this trampoline.
+    let code: YR_MODULE_FUNC = Some(function_trampoline::<F>);
+    let arguments_fmt = match (*function)
+        .prototypes
+        .iter()
+        .find(|prototype| same_code(prototype.code, code) && !prototype.arguments_fmt.is_null())
+    {
+        Some(prototype) => CStr::from_ptr(prototype.arguments_fmt).to_bytes(),
+        None => return yara_sys::ERROR_INTERNAL_FATAL_ERROR as c_int,
+    };
+    let context = FunctionContext {
+        arguments: if arguments_fmt.is_empty() {
+            &[]
+        } else {
+            std::slice::from_raw_parts(arguments, arguments_fmt.len())
+        },
+        arguments_fmt,
+        context,
+        function,
+    };
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        // Safety: F is zero-sized, checked by ZeroSized, so it has no bytes to initialize.
+        let f: F = std::mem::zeroed();
+        f(&context)
+    }));
+    let value = match result {
+        Ok(value) => value,
+        Err(_) => return yara_sys::ERROR_INTERNAL_FATAL_ERROR as c_int,
+    };
+
+    let return_obj = (*function).return_obj;
+    let result = match ((*return_obj).type_ as u32, value) {
+        (yara_sys::OBJECT_TYPE_INTEGER, ReturnValue::Integer(v)) => {
+            object_set_integer(return_obj, None, v)
+        }
+        (yara_sys::OBJECT_TYPE_INTEGER, ReturnValue::Undefined) => {
+            object_set_integer(return_obj, None, yara_sys::YR_UNDEFINED)
+        }
+        (yara_sys::OBJECT_TYPE_FLOAT, ReturnValue::Float(v)) => {
+            object_set_float(return_obj, None, v)
+        }
+        (yara_sys::OBJECT_TYPE_FLOAT, ReturnValue::Undefined) => {
+            object_set_float(return_obj, None, f64::NAN)
+        }
+        (yara_sys::OBJECT_TYPE_STRING, ReturnValue::String(v)) => {
+            object_set_string(return_obj, None, Some(&v))
+        }
+        (yara_sys::OBJECT_TYPE_STRING, ReturnValue::Undefined) => {
+            object_set_string(return_obj, None, None)
+        }
+        _ => return yara_sys::ERROR_WRONG_TYPE as c_int,
+    };
+    to_code(result)
+}
+
+fn to_code(result: Result<(), YaraError>) -> c_int {
+    match result {
+        Ok(()) => yara_sys::ERROR_SUCCESS as c_int,
+        Err(error) => error.kind.to_code(),
+    }
+}
+
+fn call_module<F>(f: F) -> c_int
+where
+    F: FnOnce() -> Result<(), YaraError>,
+{
+    let result = catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(YaraErrorKind::InternalFatalError.into()));
+    to_code(result)
+}
+
+#[doc(hidden)]
+pub unsafe fn __declarations<M: Module>(module_object: *mut c_void) -> c_int {
+    call_module(|| M::declarations(&mut Declarations::new(module_object.cast())))
+}
+
+#[doc(hidden)]
+pub unsafe fn __load<M: Module>(
+    context: *mut c_void,
+    module_object: *mut c_void,
+    module_data: *mut c_void,
+    module_data_size: usize,
+) -> c_int {
+    let module_data = if module_data.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(
+            module_data as *const u8,
+            module_data_size,
+        ))
+    };
+    call_module(|| {
+        M::load(
+            &mut ModuleObject::new(module_object.cast()),
+            &mut ScanData::new(context.cast()),
+            module_data,
+        )
+    })
+}
+
+#[doc(hidden)]
+pub unsafe fn __unload<M: Module>(module_object: *mut c_void) -> c_int {
+    let mut module = ModuleObject::new(module_object.cast());
+    let result = call_module(|| M::unload(&mut module));
+    module.drop_data();
+    result
+}
+
+/// Export a [`Module`](crate::module::Module) to libyara.
+///
+/// `$name` is the name of the module in the rules, which must also be listed in the
+/// `YARA_RUST_MODULES` environment variable. See the [`module`](crate::module) documentation.
+///
+/// # Linking
+///
+/// This defines the `<name>__declarations`, `<name>__load` and `<name>__unload` symbols,
+/// which libyara needs at link time once the module is listed in `YARA_RUST_MODULES`.
+/// Every binary linked with such a libyara must thus invoke this macro, itself or through
+/// a crate it references, for each listed module. See
+/// [Linking](crate::module#linking).
+#[macro_export]
+macro_rules! declare_module {
+    ($name:ident, $module:ty) => {
+        const _: () = {
+            #[export_name = concat!(stringify!($name), "__declarations")]
+            unsafe extern "C" fn declarations(
+                module_object: *mut ::std::ffi::c_void,
+            ) -> ::std::os::raw::c_int {
+                $crate::module::__declarations::<$module>(module_object)
+            }
+
+            #[export_name = concat!(stringify!($name), "__load")]
+            unsafe extern "C" fn load(
+                context: *mut ::std::ffi::c_void,
+                module_object: *mut ::std::ffi::c_void,
+                module_data: *mut ::std::ffi::c_void,
+                module_data_size: usize,
+            ) -> ::std::os::raw::c_int {
+                $crate::module::__load::<$module>(
+                    context,
+                    module_object,
+                    module_data,
+                    module_data_size,
+                )
+            }
+
+            #[export_name = concat!(stringify!($name), "__unload")]
+            unsafe extern "C" fn unload(
+                module_object: *mut ::std::ffi::c_void,
+            ) -> ::std::os::raw::c_int {
+                $crate::module::__unload::<$module>(module_object)
+            }
+
+            #[export_name = concat!(stringify!($name), "__initialize")]
+            extern "C" fn initialize(_module: *mut ::std::ffi::c_void) -> ::std::os::raw::c_int {
+                0
+            }
+
+            #[export_name = concat!(stringify!($name), "__finalize")]
+            extern "C" fn finalize(_module: *mut ::std::ffi::c_void) -> ::std::os::raw::c_int {
+                0
+            }
+        };
+    };
+}
@@ -0,0 +1,81 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::internals::{YrObject, YrObjectValue};
+
+/// An owned copy of the data of a module.
+///
+/// Contrary to the [`YrObject`] of a
+/// [`CallbackMsg::ModuleImported`](crate::CallbackMsg::ModuleImported), it stays valid
+/// after the scan. See [`ScanOptions::capture_modules`](crate::ScanOptions::capture_modules).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModuleData {
+    /// Name of the module.
+    pub name: String,
+    /// Data of the module, usually a [`Structure`](OwnedYrObjectValue::Structure).
+    pub value: OwnedYrObjectValue,
+}
+
+impl From<&YrObject<'_>> for ModuleData {
+    fn from(module: &YrObject<'_>) -> Self {
+        ModuleData {
+            name: String::from_utf8_lossy(module.identifier().unwrap_or_default()).into_owned(),
+            value: OwnedYrObjectValue::from(module),
+        }
+    }
+}
+
+/// An owned version of [`YrObjectValue`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OwnedYrObjectValue {
+    Integer(i64),
+    Float(f64),
+    String(Vec<u8>),
+    Array(Vec<Option<OwnedYrObjectValue>>),
+    /// Keys and values, in insertion order.
+    Dictionary(Vec<(Vec<u8>, OwnedYrObjectValue)>),
+    /// Identifiers and values of the members, without the functions.
+    Structure(Vec<(String, OwnedYrObjectValue)>),
+    Undefined,
+}
+
+impl From<&YrObject<'_>> for OwnedYrObjectValue {
+    fn from(object: &YrObject<'_>) -> Self {
+        match object.value() {
+            YrObjectValue::Integer(i) => OwnedYrObjectValue::Integer(i),
+            YrObjectValue::Float(f) => OwnedYrObjectValue::Float(f),
+            YrObjectValue::String(s) => OwnedYrObjectValue::String(s.to_vec()),
+            YrObjectValue::Array(items) => OwnedYrObjectValue::Array(
+                items
+                    .iter()
+                    .map(|item| item.as_ref().map(OwnedYrObjectValue::from))
+                    .collect(),
+            ),
+            YrObjectValue::Dictionary(_) => OwnedYrObjectValue::Dictionary(
+                object
+                    .iter_dict()
+                    .into_iter()
+                    .flatten()
+                    .map(|(key, value)| (key.to_vec(), OwnedYrObjectValue::from(&value)))
+                    .collect(),
+            ),
+            YrObjectValue::Structure(members) => OwnedYrObjectValue::Structure(
+                members
+                    .iter()
+                    .filter(|member| !matches!(member.value(), YrObjectValue::Function))
+                    .map(|member| {
+                        let identifier = member.identifier().unwrap_or_default();
+                        (
+                            String::from_utf8_lossy(identifier).into_owned(),
+                            OwnedYrObjectValue::from(member),
+                        )
+                    })
+                    .collect(),
+            ),
+            // Functions only appear as members of structures, and are skipped there.
+            YrObjectValue::Function | YrObjectValue::Undefined => OwnedYrObjectValue::Undefined,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::cancellation::CancellationToken;
+use crate::internals::YrObject;
+
+/// Options of the `scan_*_owned` methods of [`Scanner`](crate::Scanner) and
+/// [`Rules`](crate::Rules).
+///
+/// # Example
+///
+/// ```
+/// # use yara::{CancellationToken, Compiler, ScanOptions};
+/// let rules = Compiler::new()?
+///     .add_rules_str("import \"tests\" rule always { condition: true }")?
+///     .compile_rules()?;
+/// let options = ScanOptions::new()
+///     .cancellation_token(CancellationToken::new())
+///     .capture_modules(["tests"]);
+/// let outcome = rules.scanner()?.scan_mem_owned(b"", &options);
+/// assert_eq!(1, outcome.matches.len());
+/// assert_eq!("tests", outcome.modules[0].name);
+/// # Ok::<(), yara::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    cancellation_token: Option<CancellationToken>,
+    captured_modules: Vec<String>,
+}
+
+impl ScanOptions {
+    /// Create the default options: the scan cannot be cancelled and no module data is
+    /// captured.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a token to cancel the scan from another thread. See [`CancellationToken`].
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Set the modules whose data is copied to
+    /// [`ScanOutcome::modules`](crate::ScanOutcome::modules), when imported by the rules.
+    pub fn capture_modules<I, S>(mut self, modules: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.captured_modules = modules.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub(crate) fn token(&self) -> Option<&CancellationToken> {
+        self.cancellation_token.as_ref()
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.token().map_or(false, |token| token.is_cancelled())
+    }
+
+    /// Whether the data of `module` is captured.
+    pub(crate) fn captures(&self, module: &YrObject) -> bool {
+        let name = module.identifier().unwrap_or_default();
+        self.captured_modules.iter().any(|m| m.as_bytes() == name)
+    }
+}
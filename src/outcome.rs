@@ -0,0 +1,90 @@
+use crate::errors::*;
+use crate::internals::{CallbackMsg, CallbackReturn};
+use crate::module_data::ModuleData;
+use crate::options::ScanOptions;
+use crate::rules::OwnedRule;
+
+/// The result of a `scan_*_owned` call: the rules that matched, the data of the
+/// captured modules, and the error that interrupted the scan, if any.
+///
+/// Contrary to the other `scan_*` methods, the rules reported before an error are kept.
+/// Note that Yara reports the matching rules only once the data is scanned and all the
+/// rules are evaluated: a scan that times out before that has no matches.
+#[derive(Debug)]
+pub struct ScanOutcome {
+    /// Rules that matched before the scan ended.
+    pub matches: Vec<OwnedRule>,
+    /// Data of the modules imported by the rules, in import order, if captured with
+    /// [`ScanOptions::capture_modules`].
+    pub modules: Vec<ModuleData>,
+    /// Error that interrupted the scan.
+    pub error: Option<Error>,
+}
+
+impl ScanOutcome {
+    /// Whether the scan went to completion.
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Convert to the result of the corresponding `scan_*` method, dropping the
+    /// matches if there was an error.
+    pub fn into_result(self) -> Result<Vec<OwnedRule>, Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.matches),
+        }
+    }
+
+    /// An outcome of a scan that could not start.
+    pub(crate) fn failed(error: impl Into<Error>) -> Self {
+        ScanOutcome {
+            matches: Vec::new(),
+            modules: Vec::new(),
+            error: Some(error.into()),
+        }
+    }
+
+    /// Run a `scan_*_callback` method, collecting the matching rules and the captured
+    /// modules until the scan is cancelled.
+    ///
+    /// The scan is reported as cancelled only if it was stopped because of the token.
+    pub(crate) fn collect<'r, E: Into<Error>>(
+        options: &ScanOptions,
+        scan: impl FnOnce(&mut dyn FnMut(CallbackMsg<'r>) -> CallbackReturn) -> Result<(), E>,
+    ) -> Self {
+        if options.is_cancelled() {
+            return Self::failed(Error::Cancelled);
+        }
+
+        let mut matches = Vec::new();
+        let mut modules = Vec::new();
+        let mut aborted = false;
+        let mut callback = |message| {
+            if options.is_cancelled() {
+                aborted = true;
+                return CallbackReturn::Abort;
+            }
+            match message {
+                CallbackMsg::RuleMatching(rule) => matches.push(OwnedRule::from(rule)),
+                CallbackMsg::ModuleImported(module) if options.captures(&module) => {
+                    modules.push(ModuleData::from(&module))
+                }
+                _ => {}
+            }
+            CallbackReturn::Continue
+        };
+        let error = scan(&mut callback).err().map(Into::into);
+        let error = if aborted {
+            Some(Error::Cancelled)
+        } else {
+            error
+        };
+
+        ScanOutcome {
+            matches,
+            modules,
+            error,
+        }
+    }
+}
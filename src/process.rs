@@ -0,0 +1,534 @@
+//! Scanning of selected memory regions of Linux processes.
+//!
+//! [`Scanner::scan_process_with_regions`](crate::Scanner::scan_process_with_regions) maps
+//! the matches of a whole process scan to the [`MemoryRegion`] they were found in.
+//!
+//! Contrary to [`Scanner::scan_process`](crate::Scanner::scan_process), which scans every
+//! readable region of a process, a [`ProcessMemoryIterator`] only reads the regions of
+//! `/proc/<pid>/maps` accepted by a [`RegionFilter`], from `/proc/<pid>/mem`.
+//!
+//! Reading the memory of a process requires the same permissions as attaching to it with
+//! `ptrace`. The process is not stopped during the scan.
+//!
+//! # Example
+//!
+//! Scan only the heap and the anonymous executable regions of a process:
+//!
+//! ```no_run
+//! # use yara::Compiler;
+//! use yara::process::{ProcessMemoryIterator, RegionFilter};
+//!
+//! # let pid = 1;
+//! let rules = Compiler::new()?
+//!     .add_rules_str("rule contains_rust { strings: $rust = \"rust\" condition: $rust }")?
+//!     .compile_rules()?;
+//! let scanner = rules.scanner()?;
+//!
+//! let heap = ProcessMemoryIterator::new(pid, RegionFilter::new().path("[heap]"))?;
+//! let heap_results = scanner.scan_mem_blocks(heap)?;
+//!
+//! let injected = RegionFilter::new().anonymous(true).executable(true);
+//! let injected_results = scanner.scan_mem_blocks(ProcessMemoryIterator::new(pid, injected)?)?;
+//! # Ok::<(), yara::Error>(())
+//! ```
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::FileExt;
+
+use crate::errors::*;
+use crate::glob::glob_match;
+use crate::internals::{MemoryBlock, MemoryBlockIterator, MemoryBlockIteratorSized};
+use crate::matches::Match;
+use crate::rules::OwnedRule;
+use crate::string::OwnedYrString;
+
+/// Permissions of a memory region.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    /// Whether the region is shared, instead of private (copy on write).
+    pub shared: bool,
+}
+
+impl fmt::Display for Permissions {
+    /// Format the permissions like `/proc/<pid>/maps`, e.g. `r-xp`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flag = |set, c| if set { c } else { '-' };
+        write!(
+            f,
+            "{}{}{}{}",
+            flag(self.read, 'r'),
+            flag(self.write, 'w'),
+            flag(self.execute, 'x'),
+            if self.shared { 's' } else { 'p' }
+        )
+    }
+}
+
+/// A memory region of a process, as listed in `/proc/<pid>/maps`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoryRegion {
+    /// Start address of the region.
+    pub start: u64,
+    /// End address of the region, excluded.
+    pub end: u64,
+    pub permissions: Permissions,
+    /// Offset of the region in the backing file.
+    pub offset: u64,
+    /// Inode of the backing file, 0 if none.
+    pub inode: u64,
+    /// Path of the backing file, or pseudo-path like `[heap]` or `[stack]`.
+    ///
+    /// `None` for anonymous mappings.
+    pub path: Option<String>,
+}
+
+impl MemoryRegion {
+    /// Size of the region, in bytes.
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Whether the region is not backed by a file, like the heap, the stacks or anonymous
+    /// mappings.
+    pub fn is_anonymous(&self) -> bool {
+        match &self.path {
+            Some(path) => path.starts_with('['),
+            None => true,
+        }
+    }
+
+    /// Parse a line of `/proc/<pid>/maps`.
+    fn parse(line: &str) -> Option<MemoryRegion> {
+        let mut fields = line.splitn(6, ' ');
+        let (start, end) = fields.next()?.split_once('-')?;
+        let permissions = fields.next()?.as_bytes();
+        let offset = fields.next()?;
+        let _device = fields.next()?;
+        let inode = fields.next()?;
+        let path = fields.next().map(str::trim_start).unwrap_or_default();
+
+        if permissions.len() != 4 {
+            return None;
+        }
+        Some(MemoryRegion {
+            start: u64::from_str_radix(start, 16).ok()?,
+            end: u64::from_str_radix(end, 16).ok()?,
+            permissions: Permissions {
+                read: permissions[0] == b'r',
+                write: permissions[1] == b'w',
+                execute: permissions[2] == b'x',
+                shared: permissions[3] == b's',
+            },
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            inode: inode.parse().ok()?,
+            path: if path.is_empty() {
+                None
+            } else {
+                Some(path.to_owned())
+            },
+        })
+    }
+}
+
+/// Read the memory regions of a process.
+pub fn memory_regions(pid: u32) -> Result<Vec<MemoryRegion>, Error> {
+    let maps = fs::read_to_string(format!("/proc/{pid}/maps"))
+        .map_err(|e| IoError::new(e, IoErrorKind::ReadingProcessMaps))?;
+    Ok(maps.lines().filter_map(MemoryRegion::parse).collect())
+}
+
+/// The results of a process scan, with the memory regions of the process.
+///
+/// Returned by [`Scanner::scan_process_with_regions`](crate::Scanner::scan_process_with_regions).
+#[derive(Debug)]
+pub struct ProcessScanResults {
+    /// The matching rules.
+    pub rules: Vec<OwnedRule>,
+    /// The memory regions of the process, as listed in `/proc/<pid>/maps` while the process
+    /// was paused for the scan, sorted by address.
+    pub regions: Vec<MemoryRegion>,
+}
+
+impl ProcessScanResults {
+    /// Get the region containing the address `address`.
+    pub fn region_at(&self, address: u64) -> Option<&MemoryRegion> {
+        let index = self
+            .regions
+            .partition_point(|region| region.start <= address);
+        index
+            .checked_sub(1)
+            .map(|index| &self.regions[index])
+            .filter(|region| address < region.end)
+    }
+
+    /// Get the region a match was found in.
+    pub fn region(&self, m: &Match) -> Option<&MemoryRegion> {
+        self.region_at((m.base + m.offset) as u64)
+    }
+
+    /// Iterate over the matches of all the rules, with their region.
+    pub fn matches(&self) -> impl Iterator<Item = RegionMatch<'_>> {
+        self.rules.iter().flat_map(move |rule| {
+            rule.strings.iter().flat_map(move |string| {
+                string.matches.iter().map(move |matched| RegionMatch {
+                    rule,
+                    string,
+                    matched,
+                    region: self.region(matched),
+                })
+            })
+        })
+    }
+}
+
+/// A match of a process scan, with the memory region it was found in.
+#[derive(Clone, Copy, Debug)]
+pub struct RegionMatch<'a> {
+    pub rule: &'a OwnedRule,
+    pub string: &'a OwnedYrString,
+    pub matched: &'a Match,
+    /// The region containing the match, `None` if it was not listed in the maps of the process.
+    pub region: Option<&'a MemoryRegion>,
+}
+
+/// Selection of the memory regions to scan.
+///
+/// Only readable regions are scanned. By default, all of them are selected; each setting
+/// further restricts the selection.
+#[derive(Clone, Debug, Default)]
+pub struct RegionFilter {
+    write: Option<bool>,
+    execute: Option<bool>,
+    shared: Option<bool>,
+    paths: Vec<String>,
+    anonymous: Option<bool>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+}
+
+impl RegionFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select only writable, or non-writable, regions.
+    pub fn writable(mut self, writable: bool) -> Self {
+        self.write = Some(writable);
+        self
+    }
+
+    /// Select only executable, or non-executable, regions.
+    pub fn executable(mut self, executable: bool) -> Self {
+        self.execute = Some(executable);
+        self
+    }
+
+    /// Select only shared, or private, regions.
+    pub fn shared(mut self, shared: bool) -> Self {
+        self.shared = Some(shared);
+        self
+    }
+
+    /// Select the regions whose path matches a glob pattern, like `[heap]`,
+    /// `[stack*` or `/usr/lib/**`.
+    ///
+    /// Can be called multiple times to select the regions matching any of the patterns.
+    /// Anonymous mappings, which have no path, never match.
+    pub fn path(mut self, pattern: impl Into<String>) -> Self {
+        self.paths.push(pattern.into());
+        self
+    }
+
+    /// Select only anonymous, or file-backed, regions.
+    ///
+    /// See [`MemoryRegion::is_anonymous`].
+    pub fn anonymous(mut self, anonymous: bool) -> Self {
+        self.anonymous = Some(anonymous);
+        self
+    }
+
+    /// Select only the regions of at least `min_size` bytes.
+    pub fn min_size(mut self, min_size: u64) -> Self {
+        self.min_size = Some(min_size);
+        self
+    }
+
+    /// Select only the regions of at most `max_size` bytes.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Whether the filter selects `region`.
+    pub fn matches(&self, region: &MemoryRegion) -> bool {
+        let permissions = &region.permissions;
+        permissions.read
+            && self.write.map_or(true, |w| w == permissions.write)
+            && self.execute.map_or(true, |x| x == permissions.execute)
+            && self.shared.map_or(true, |s| s == permissions.shared)
+            && (self.paths.is_empty()
+                || region.path.as_ref().map_or(false, |path| {
+                    self.paths.iter().any(|pattern| glob_match(pattern, path))
+                }))
+            && self.anonymous.map_or(true, |a| a == region.is_anonymous())
+            && self.min_size.map_or(true, |min| region.size() >= min)
+            && self.max_size.map_or(true, |max| region.size() <= max)
+    }
+}
+
+/// A [`MemoryBlockIterator`] over the memory regions of a Linux process.
+///
+/// The regions are listed when the iterator is created. Regions larger than the maximum
+/// block size are read in several blocks, without overlap. Regions that can not be read,
+/// like `[vvar]`, or that were unmapped since, are skipped.
+///
+/// `filesize` is the total size of the selected regions.
+#[derive(Debug)]
+pub struct ProcessMemoryIterator {
+    mem: File,
+    regions: Vec<MemoryRegion>,
+    max_block_size: u64,
+    /// Index of the current region.
+    region: usize,
+    /// Address of the next block in the current region.
+    address: u64,
+    buffer: Vec<u8>,
+}
+
+impl ProcessMemoryIterator {
+    /// Default maximum size of the blocks: 16 MiB.
+    pub const DEFAULT_MAX_BLOCK_SIZE: u64 = 16 * 1024 * 1024;
+
+    /// Select the regions of process `pid` accepted by `filter`.
+    pub fn new(pid: u32, filter: RegionFilter) -> Result<Self, Error> {
+        let mut regions = memory_regions(pid)?;
+        regions.retain(|region| filter.matches(region));
+        let mem = File::open(format!("/proc/{pid}/mem"))
+            .map_err(|e| IoError::new(e, IoErrorKind::OpeningProcessMemory))?;
+        Ok(Self {
+            mem,
+            regions,
+            max_block_size: Self::DEFAULT_MAX_BLOCK_SIZE,
+            region: 0,
+            address: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Set the maximum size of the blocks, in bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_block_size` is 0.
+    pub fn max_block_size(mut self, max_block_size: u64) -> Self {
+        assert!(max_block_size > 0, "the block size must not be 0");
+        self.max_block_size = max_block_size;
+        self
+    }
+
+    /// The selected regions.
+    pub fn regions(&self) -> &[MemoryRegion] {
+        &self.regions
+    }
+
+    /// Read the block at `address` of the current region.
+    fn read_block(&mut self, address: u64) -> io::Result<()> {
+        let region = &self.regions[self.region];
+        let size = (region.end - address).min(self.max_block_size) as usize;
+        self.buffer.resize(size, 0);
+        let mut filled = 0;
+        while filled < size {
+            match self
+                .mem
+                .read_at(&mut self.buffer[filled..], address + filled as u64)
+            {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.buffer.truncate(filled);
+        if filled == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    /// Read the next readable block, starting from `self.address` in `self.region`.
+    fn next_block(&mut self) -> Option<MemoryBlock<'_>> {
+        while self.region < self.regions.len() {
+            let address = self.address;
+            if address < self.regions[self.region].end && self.read_block(address).is_ok() {
+                self.address = address + self.buffer.len() as u64;
+                return Some(MemoryBlock::new(address, &self.buffer));
+            }
+            self.region += 1;
+            self.address = self.regions.get(self.region).map_or(0, |r| r.start);
+        }
+        None
+    }
+}
+
+impl MemoryBlockIterator for ProcessMemoryIterator {
+    fn first(&mut self) -> Option<MemoryBlock<'_>> {
+        self.region = 0;
+        self.address = self.regions.first().map_or(0, |r| r.start);
+        self.next_block()
+    }
+
+    fn next(&mut self) -> Option<MemoryBlock<'_>> {
+        self.next_block()
+    }
+}
+
+impl MemoryBlockIteratorSized for ProcessMemoryIterator {
+    fn file_size(&mut self) -> u64 {
+        self.regions.iter().map(MemoryRegion::size).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::process::{Command, Stdio};
+
+    use super::*;
+    use crate::Compiler;
+
+    #[test]
+    fn parse_maps() {
+        let region = MemoryRegion::parse(
+            "7f2c4a1b5000-7f2c4a1b7000 r-xp 00002000 08:01 1234                       /usr/lib/libc so.6 (deleted)",
+        )
+        .unwrap();
+        assert_eq!(region.start, 0x7f2c4a1b5000);
+        assert_eq!(region.size(), 0x2000);
+        assert_eq!(region.permissions.to_string(), "r-xp");
+        assert_eq!(region.offset, 0x2000);
+        assert_eq!(region.inode, 1234);
+        assert_eq!(region.path.as_deref(), Some("/usr/lib/libc so.6 (deleted)"));
+        assert!(!region.is_anonymous());
+
+        let heap =
+            MemoryRegion::parse("55d0c8e3a000-55d0c8e5b000 rw-p 00000000 00:00 0 [heap]").unwrap();
+        assert!(heap.is_anonymous());
+        let anonymous =
+            MemoryRegion::parse("7f2c4a000000-7f2c4a021000 rwxs 00000000 00:00 0 ").unwrap();
+        assert_eq!(anonymous.path, None);
+        assert!(anonymous.permissions.shared);
+        assert!(anonymous.is_anonymous());
+
+        let filter = RegionFilter::new().anonymous(true).writable(true);
+        assert!(filter.matches(&heap));
+        assert!(!filter.matches(&region));
+        assert!(RegionFilter::new().path("[heap]").matches(&heap));
+        assert!(!RegionFilter::new().path("[heap]").matches(&anonymous));
+        assert!(RegionFilter::new().path("/usr/lib/**").matches(&region));
+        assert!(!RegionFilter::new().min_size(0x3000).matches(&region));
+    }
+
+    #[test]
+    fn process_memory_iterator() {
+        let rules = Compiler::new()
+            .unwrap()
+            .add_rules_str(
+                r#"rule found_uuid {
+                    strings:
+                        $target = "22ed3f4e-84e9-4a4f-9a32-cb1d4ae5e3e6"
+                    condition:
+                        $target
+                }"#,
+            )
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let scanner = rules.scanner().unwrap();
+        let mut process = Command::new("sh")
+            .arg("-c")
+            .arg("sleep 5; echo 22ed3f4e-84e9-4a4f-9a32-cb1d4ae5e3e6")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let anonymous =
+            ProcessMemoryIterator::new(process.id(), RegionFilter::new().anonymous(true)).unwrap();
+        assert!(anonymous.regions().iter().all(MemoryRegion::is_anonymous));
+        let results = scanner.scan_mem_blocks_sized(anonymous).unwrap();
+        assert_eq!(1, results.len());
+
+        let code = RegionFilter::new().anonymous(false).executable(true);
+        let code = ProcessMemoryIterator::new(process.id(), code).unwrap();
+        let results = scanner.scan_mem_blocks_sized(code).unwrap();
+        assert_eq!(0, results.len());
+
+        process.kill().unwrap();
+        process.wait().unwrap();
+    }
+
+    #[test]
+    fn region_at() {
+        let region = |start, end| MemoryRegion {
+            start,
+            end,
+            permissions: Permissions::default(),
+            offset: 0,
+            inode: 0,
+            path: None,
+        };
+        let results = ProcessScanResults {
+            rules: Vec::new(),
+            regions: vec![region(0x1000, 0x2000), region(0x3000, 0x5000)],
+        };
+        assert_eq!(results.region_at(0xfff), None);
+        assert_eq!(results.region_at(0x1000).map(|r| r.start), Some(0x1000));
+        assert_eq!(results.region_at(0x1fff).map(|r| r.start), Some(0x1000));
+        assert_eq!(results.region_at(0x2000), None);
+        assert_eq!(results.region_at(0x4000).map(|r| r.start), Some(0x3000));
+        assert_eq!(results.region_at(0x5000), None);
+    }
+
+    #[test]
+    fn scan_process_with_regions() {
+        let rules = Compiler::new()
+            .unwrap()
+            .add_rules_str(
+                r#"rule found_uuid {
+                    strings:
+                        $target = "5b0b8c1e-2f8d-4f7e-9d0e-3c4f0f6a8b21"
+                    condition:
+                        $target
+                }"#,
+            )
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let mut scanner = rules.scanner().unwrap();
+        let mut process = Command::new("sh")
+            .arg("-c")
+            .arg("sleep 5; echo 5b0b8c1e-2f8d-4f7e-9d0e-3c4f0f6a8b21")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let results = scanner.scan_process_with_regions(process.id()).unwrap();
+        assert_eq!(1, results.rules.len());
+        assert!(!results.regions.is_empty());
+        let matches: Vec<_> = results.matches().collect();
+        assert!(!matches.is_empty());
+        for m in matches {
+            assert_eq!("found_uuid", m.rule.identifier);
+            assert_eq!("$target", m.string.identifier);
+            let region = m.region.expect("match should be in a region");
+            assert!(region.permissions.read);
+            assert!(region.is_anonymous());
+        }
+
+        process.kill().unwrap();
+        process.wait().unwrap();
+    }
+}
@@ -0,0 +1,17 @@
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Profiling information about a rule.
+///
+/// Obtained from [`Scanner::profiling_info`](crate::Scanner::profiling_info).
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RuleProfilingInfo<'r> {
+    /// Name of the rule.
+    pub identifier: &'r str,
+    /// Namespace of the rule.
+    pub namespace: &'r str,
+    /// Cost of the rule, as computed by libyara from the time spent evaluating its
+    /// condition and matching its strings.
+    pub cost: u64,
+}
@@ -0,0 +1,187 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::internals::{MemoryBlock, MemoryBlockIterator, MemoryBlockIteratorSized};
+
+/// A [`MemoryBlockIteratorSized`] reading a [`Read`] + [`Seek`] source in blocks.
+///
+/// The whole source is scanned, from its start. Each block repeats the last `overlap`
+/// bytes of the previous one, so strings spanning two blocks are still found as long as they
+/// are not longer than the overlap. `filesize` is the length of the source.
+///
+/// The iterator can not report errors to Yara: the first I/O error ends the iteration and is
+/// kept, to be retrieved with [`take_error`](Self::take_error) after the scan.
+///
+/// Used by [`Scanner::scan_reader`](crate::Scanner::scan_reader).
+#[derive(Debug)]
+pub struct ReaderMemoryBlockIterator<R> {
+    reader: R,
+    block_size: usize,
+    overlap: usize,
+    buffer: Vec<u8>,
+    base: u64,
+    error: Option<io::Error>,
+}
+
+impl<R: Read + Seek> ReaderMemoryBlockIterator<R> {
+    /// Default size of the blocks: 1 MiB.
+    pub const DEFAULT_BLOCK_SIZE: usize = 1024 * 1024;
+    /// Default overlap between blocks: 4 KiB.
+    pub const DEFAULT_OVERLAP: usize = 4 * 1024;
+
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            block_size: Self::DEFAULT_BLOCK_SIZE,
+            overlap: Self::DEFAULT_OVERLAP,
+            buffer: Vec::new(),
+            base: 0,
+            error: None,
+        }
+    }
+
+    /// Set the size of the blocks, and how many bytes of a block are repeated at the start
+    /// of the next one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `overlap` is not smaller than `block_size`.
+    pub fn with_block_size(mut self, block_size: usize, overlap: usize) -> Self {
+        assert!(
+            overlap < block_size,
+            "the overlap must be smaller than the block size"
+        );
+        self.block_size = block_size;
+        self.overlap = overlap;
+        self
+    }
+
+    /// Take the I/O error that ended the iteration, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Get back the reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Fill the buffer up to the block size, or until the end of the reader.
+    ///
+    /// Returns `true` if new bytes were read.
+    fn fill_buffer(&mut self) -> io::Result<bool> {
+        let start = self.buffer.len();
+        let mut filled = start;
+        self.buffer.resize(self.block_size, 0);
+        let result = loop {
+            if filled == self.block_size {
+                break Ok(());
+            }
+            match self.reader.read(&mut self.buffer[filled..]) {
+                Ok(0) => break Ok(()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.buffer.truncate(filled);
+        result.map(|()| filled > start)
+    }
+
+    fn block(&mut self, result: io::Result<bool>) -> Option<MemoryBlock<'_>> {
+        match result {
+            Ok(true) => Some(MemoryBlock::new(self.base, &self.buffer)),
+            Ok(false) => None,
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    fn stream_len(&mut self) -> io::Result<u64> {
+        let position = self.reader.stream_position()?;
+        let len = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(position))?;
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> MemoryBlockIterator for ReaderMemoryBlockIterator<R> {
+    fn first(&mut self) -> Option<MemoryBlock<'_>> {
+        self.buffer.clear();
+        self.base = 0;
+        self.error = None;
+        let result = self
+            .reader
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.fill_buffer());
+        self.block(result)
+    }
+
+    fn next(&mut self) -> Option<MemoryBlock<'_>> {
+        if self.error.is_some() || self.buffer.len() < self.block_size {
+            // The previous block reached the end of the reader.
+            return None;
+        }
+        let consumed = self.buffer.len() - self.overlap;
+        self.buffer.drain(..consumed);
+        self.base += consumed as u64;
+        let result = self.fill_buffer();
+        self.block(result)
+    }
+}
+
+impl<R: Read + Seek> MemoryBlockIteratorSized for ReaderMemoryBlockIterator<R> {
+    fn file_size(&mut self) -> u64 {
+        match self.stream_len() {
+            Ok(len) => len,
+            Err(e) => {
+                self.error = Some(e);
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::internals::collect_blocks;
+
+    fn blocks(data: &[u8], block_size: usize, overlap: usize) -> Vec<(u64, Vec<u8>)> {
+        let mut iter =
+            ReaderMemoryBlockIterator::new(Cursor::new(data)).with_block_size(block_size, overlap);
+        let blocks = collect_blocks(&mut iter);
+        assert!(iter.take_error().is_none());
+        blocks
+    }
+
+    #[test]
+    fn overlapping_blocks() {
+        assert_eq!(
+            blocks(b"abcdefghij", 4, 1),
+            vec![
+                (0, b"abcd".to_vec()),
+                (3, b"defg".to_vec()),
+                (6, b"ghij".to_vec()),
+            ]
+        );
+        assert_eq!(
+            blocks(b"abcdefgh", 4, 0),
+            vec![(0, b"abcd".to_vec()), (4, b"efgh".to_vec())]
+        );
+        assert_eq!(blocks(b"ab", 4, 2), vec![(0, b"ab".to_vec())]);
+        assert_eq!(blocks(b"", 4, 2), vec![]);
+    }
+
+    #[test]
+    fn file_size() {
+        let mut iter =
+            ReaderMemoryBlockIterator::new(Cursor::new(b"abcdefghij")).with_block_size(4, 1);
+        assert!(iter.first().is_some());
+        assert_eq!(iter.file_size(), 10);
+        assert_eq!(iter.next().map(|b| b.base()), Some(3));
+    }
+}
@@ -1,4 +1,4 @@
-use std::fs::File;
+use std::ffi::CStr;
 use std::io::{Read, Write};
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
@@ -9,11 +9,14 @@ use std::path::Path;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::compiler::CompilerVariableValue;
 use crate::errors::*;
 use crate::flags::ScanFlags;
 use crate::initialize::InitializationToken;
 use crate::internals::{self, CallbackMsg, CallbackReturn};
-use crate::string::YrString;
+use crate::options::ScanOptions;
+use crate::outcome::ScanOutcome;
+use crate::string::{OwnedYrString, RuleString, YrString};
 
 /// A set of compiled rules.
 ///
@@ -22,7 +25,7 @@ use crate::string::YrString;
 pub struct Rules {
     pub(crate) inner: *mut yara_sys::YR_RULES,
     pub(crate) _token: InitializationToken,
-    flags: ScanFlags,
+    pub(crate) flags: ScanFlags,
 }
 
 // On the subject of thread-safety:
@@ -77,6 +80,67 @@ impl Rules {
         internals::get_rules(self.inner)
     }
 
+    /// Get statistics about the ruleset, mostly about the Aho-Corasick automaton.
+    ///
+    /// Long match lists mean that many strings share the same atoms, which makes
+    /// the scans slower.
+    pub fn stats(&self) -> Result<RulesStats, YaraError> {
+        internals::rules_get_stats(self.inner)
+    }
+
+    /// Get the external variables declared in this ruleset, with their default value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use yara::{Compiler, ExternalVariableType};
+    /// let mut compiler = Compiler::new()?;
+    /// compiler.define_variable("habitat", "land")?;
+    /// compiler.define_variable("is_cute", false)?;
+    /// let rules = compiler.compile_rules()?;
+    /// let variables = rules.external_variables();
+    /// assert_eq!(2, variables.len());
+    /// assert_eq!("habitat", variables[0].identifier);
+    /// assert_eq!(ExternalVariableType::String, variables[0].value.variable_type());
+    /// # Ok::<(), yara::Error>(())
+    /// ```
+    pub fn external_variables(&self) -> Vec<ExternalVariable<'_>> {
+        internals::get_external_variables(self.inner)
+    }
+
+    /// Change the default value of an external variable.
+    ///
+    /// The new value applies to every `scan_*` call on these rules, and to the
+    /// [`Scanner`](crate::scanner::Scanner)s created afterwards.
+    ///
+    /// Note that the variable must have already been declared with the proper type
+    /// with [define_variable](crate::Compiler::define_variable) when compiling the rules.
+    /// Otherwise, a [`VariableError`] is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use yara::Compiler;
+    /// let mut compiler = Compiler::new()?;
+    /// compiler.define_variable("habitat", "land")?;
+    /// let mut rules = compiler
+    ///     .add_rules_str(r#"rule is_ferris { condition: habitat == "ocean" }"#)?
+    ///     .compile_rules()?;
+    /// rules.define_variable("habitat", "ocean")?;
+    /// assert_eq!(1, rules.scan_mem(b"", 5)?.len());
+    /// # Ok::<(), yara::Error>(())
+    /// ```
+    pub fn define_variable<V: CompilerVariableValue>(
+        &mut self,
+        identifier: &str,
+        value: V,
+    ) -> Result<(), Error> {
+        internals::check_external_variable(self.inner, identifier, value.variable_type())?;
+        value
+            .assign_in_rules(self.inner, identifier)
+            .map_err(Into::into)
+    }
+
     /// Create a [`Scanner`](crate::scanner::Scanner) from this set of rules.
     ///
     /// You can create as many scanners as you want, and they each can have
@@ -85,6 +149,17 @@ impl Rules {
         crate::scanner::Scanner::new(self)
     }
 
+    /// Create a scanner with the timeout and the flags of a `scan_*` call.
+    ///
+    /// Contrary to the `rules_scan_*` functions, the scanner gives access to the rule
+    /// that caused an error.
+    fn timed_scanner(&self, timeout: i32) -> Result<crate::scanner::Scanner<'_>, YaraError> {
+        let mut scanner = self.scanner()?;
+        scanner.set_timeout(timeout);
+        scanner.set_flags(self.flags);
+        Ok(scanner)
+    }
+
     /// Scan memory.
     ///
     /// Returns a `Vec` of maching rules.
@@ -132,6 +207,23 @@ impl Rules {
             .map(|_| results)
     }
 
+    /// Scan memory.
+    ///
+    /// Same as [`scan_mem`](Self::scan_mem), but returns owned results which do not
+    /// borrow from the rules.
+    ///
+    /// See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `mem` - Slice to scan.
+    /// * `timeout` - the timeout is in seconds.
+    /// * `options` - Options of the scan.
+    pub fn scan_mem_owned(&self, mem: &[u8], timeout: i32, options: &ScanOptions) -> ScanOutcome {
+        match self.timed_scanner(timeout) {
+            Ok(mut scanner) => scanner.scan_mem_owned(mem, options),
+            Err(error) => ScanOutcome::failed(error),
+        }
+    }
+
     /// Scan memory with custom callback
     ///
     /// Returns
@@ -170,6 +262,28 @@ impl Rules {
             .map(|_| results)
     }
 
+    /// Scan a file.
+    ///
+    /// Same as [`scan_file`](Self::scan_file), but returns owned results which do not
+    /// borrow from the rules.
+    ///
+    /// See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `path` - Path to file
+    /// * `timeout` - the timeout is in seconds
+    /// * `options` - Options of the scan.
+    pub fn scan_file_owned<P: AsRef<Path>>(
+        &self,
+        path: P,
+        timeout: i32,
+        options: &ScanOptions,
+    ) -> ScanOutcome {
+        match self.timed_scanner(timeout) {
+            Ok(mut scanner) => scanner.scan_file_owned(path, options),
+            Err(error) => ScanOutcome::failed(error),
+        }
+    }
+
     /// Scan file with custom callback
     ///
     /// Returns
@@ -183,12 +297,8 @@ impl Rules {
         timeout: i32,
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), Error> {
-        File::open(path)
-            .map_err(|e| IoError::new(e, IoErrorKind::OpenScanFile).into())
-            .and_then(|file| {
-                internals::rules_scan_file(self.inner, &file, timeout, self.flags.bits(), callback)
-                    .map_err(|e| e.into())
-            })
+        self.timed_scanner(timeout)?
+            .scan_file_callback(path, callback)
     }
 
     /// Attach a process, pause it, and scan its memory.
@@ -213,6 +323,27 @@ impl Rules {
             .map(|_| results)
     }
 
+    /// Attach a process, pause it, and scan its memory.
+    ///
+    /// Same as [`scan_process`](Self::scan_process), but returns owned results which
+    /// do not borrow from the rules.
+    ///
+    /// See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `pid` - Process id
+    /// * `timeout` - the timeout is in seconds
+    /// * `options` - Options of the scan.
+    ///
+    /// # Permissions
+    ///
+    /// You need to be able to attach to process `pid`.
+    pub fn scan_process_owned(&self, pid: u32, timeout: i32, options: &ScanOptions) -> ScanOutcome {
+        match self.timed_scanner(timeout) {
+            Ok(mut scanner) => scanner.scan_process_owned(pid, options),
+            Err(error) => ScanOutcome::failed(error),
+        }
+    }
+
     /// Attach a process, pause it, and scan its memory.
     ///
     /// Returns
@@ -251,6 +382,28 @@ impl Rules {
             .map(|_| results)
     }
 
+    /// Scan a opened file.
+    ///
+    /// Same as [`scan_fd`](Self::scan_fd), but returns owned results which do not
+    /// borrow from the rules.
+    ///
+    /// See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `file` - the object that implements get raw file descriptor or file handle
+    /// * `timeout` - the timeout is in seconds
+    /// * `options` - Options of the scan.
+    pub fn scan_fd_owned<F: AsRawFd>(
+        &self,
+        fd: &F,
+        timeout: i32,
+        options: &ScanOptions,
+    ) -> ScanOutcome {
+        match self.timed_scanner(timeout) {
+            Ok(scanner) => scanner.scan_fd_owned(fd, options),
+            Err(error) => ScanOutcome::failed(error),
+        }
+    }
+
     /// Scan a opened file with custom callback
     ///
     /// Returns
@@ -264,8 +417,7 @@ impl Rules {
         timeout: i32,
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), Error> {
-        internals::rules_scan_file(self.inner, fd, timeout, self.flags.bits(), callback)
-            .map_err(|e| e.into())
+        self.timed_scanner(timeout)?.scan_fd_callback(fd, callback)
     }
 
     /// Save the rules to a file.
@@ -321,6 +473,66 @@ impl Drop for Rules {
     }
 }
 
+/// Statistics about a ruleset.
+///
+/// Obtained from [`Rules::stats`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RulesStats {
+    /// Number of rules.
+    pub num_rules: u32,
+    /// Number of strings.
+    pub num_strings: u32,
+    /// Number of Aho-Corasick matches, i.e. the sum of the lengths of all the match lists.
+    pub ac_matches: u32,
+    /// Length of the match list of the root node of the Aho-Corasick automaton.
+    pub ac_root_match_list_length: u32,
+    /// Average length of the non-empty match lists.
+    pub ac_average_match_list_length: f32,
+    /// Lengths of the 100 longest match lists, in decreasing order.
+    pub top_ac_match_list_lengths: Vec<u32>,
+    /// Percentiles of the match list lengths.
+    ///
+    /// `ac_match_list_length_pctls[n]` is the n-th percentile, for `n` in `0..=100`.
+    pub ac_match_list_length_pctls: Vec<u32>,
+    /// Size of the Aho-Corasick transition and match tables.
+    pub ac_tables_size: u32,
+}
+
+/// An external variable declared in a ruleset.
+///
+/// Obtained from [`Rules::external_variables`].
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExternalVariable<'r> {
+    /// Name of the variable.
+    pub identifier: &'r str,
+    /// Default value of the variable.
+    pub value: ExternalVariableValue<'r>,
+}
+
+/// Value of an [`ExternalVariable`].
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum ExternalVariableValue<'r> {
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    String(&'r CStr),
+}
+
+impl ExternalVariableValue<'_> {
+    /// Type of the value.
+    pub fn variable_type(&self) -> ExternalVariableType {
+        match self {
+            ExternalVariableValue::Integer(_) => ExternalVariableType::Integer,
+            ExternalVariableValue::Float(_) => ExternalVariableType::Float,
+            ExternalVariableValue::Boolean(_) => ExternalVariableType::Boolean,
+            ExternalVariableValue::String(_) => ExternalVariableType::String,
+        }
+    }
+}
+
 /// A rule contained in a ruleset.
 
 #[derive(Debug)]
@@ -336,9 +548,38 @@ pub struct RulesetRule<'r> {
     pub metadatas: Vec<Metadata<'r>>,
     /// Tags of the rule.
     pub tags: Vec<&'r str>,
+    /// Strings defined in the rule.
+    pub strings: Vec<RuleString<'r>>,
 }
 
 impl RulesetRule<'_> {
+    /// Whether the rule is private, i.e. is not reported when it matches.
+    pub fn is_private(&self) -> bool {
+        self.has_flag(yara_sys::RULE_FLAGS_PRIVATE)
+    }
+
+    /// Whether the rule is global, i.e. must match for any other rule of its namespace to match.
+    pub fn is_global(&self) -> bool {
+        self.has_flag(yara_sys::RULE_FLAGS_GLOBAL)
+    }
+
+    /// Whether the rule is null.
+    ///
+    /// Yara uses a null rule to mark the end of the rules table, so this is `false` for
+    /// the rules returned by [`Rules::get_rules`].
+    pub fn is_null(&self) -> bool {
+        self.has_flag(yara_sys::RULE_FLAGS_NULL)
+    }
+
+    /// Whether the rule is enabled, i.e. is evaluated during the scans.
+    pub fn is_enabled(&self) -> bool {
+        !self.has_flag(yara_sys::RULE_FLAGS_DISABLED)
+    }
+
+    fn has_flag(&self, flag: u32) -> bool {
+        (unsafe { (*self.inner).flags } as u32) & flag != 0
+    }
+
     pub fn enable(&mut self) {
         unsafe {
             (*self.inner).enable();
@@ -385,6 +626,67 @@ pub enum MetadataValue<'r> {
     Boolean(bool),
 }
 
+/// An owned version of [`Rule`].
+///
+/// Contrary to [`Rule`], it does not borrow from the [`Rules`], so it can be sent
+/// to another thread, stored, or outlive the ruleset.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedRule {
+    /// Name of the rule.
+    pub identifier: String,
+    /// Namespace of the rule.
+    pub namespace: String,
+    /// Metadatas of the rule.
+    pub metadatas: Vec<OwnedMetadata>,
+    /// Tags of the rule.
+    pub tags: Vec<String>,
+    /// Matcher strings of the rule.
+    pub strings: Vec<OwnedYrString>,
+}
+
+impl From<Rule<'_>> for OwnedRule {
+    fn from(rule: Rule<'_>) -> Self {
+        OwnedRule {
+            identifier: rule.identifier.to_owned(),
+            namespace: rule.namespace.to_owned(),
+            metadatas: rule.metadatas.iter().map(OwnedMetadata::from).collect(),
+            tags: rule.tags.into_iter().map(str::to_owned).collect(),
+            strings: rule.strings.into_iter().map(OwnedYrString::from).collect(),
+        }
+    }
+}
+
+/// An owned version of [`Metadata`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedMetadata {
+    pub identifier: String,
+    pub value: OwnedMetadataValue,
+}
+
+impl From<&Metadata<'_>> for OwnedMetadata {
+    fn from(metadata: &Metadata<'_>) -> Self {
+        OwnedMetadata {
+            identifier: metadata.identifier.to_owned(),
+            value: match metadata.value {
+                MetadataValue::Integer(i) => OwnedMetadataValue::Integer(i),
+                MetadataValue::String(s) => OwnedMetadataValue::String(s.to_owned()),
+                MetadataValue::Boolean(b) => OwnedMetadataValue::Boolean(b),
+            },
+        }
+    }
+}
+
+/// Type of the value in [`OwnedMetadata`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OwnedMetadataValue {
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+}
+
 #[cfg(test)]
 mod test {
     use std::process::{Command, Stdio};
@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+use std::ffi::c_void;
 use std::fs::File;
+use std::io::{Read, Seek};
 use std::marker::PhantomData;
 #[cfg(unix)]
 use std::os::unix::io::AsRawFd;
@@ -6,13 +9,21 @@ use std::os::unix::io::AsRawFd;
 use std::os::windows::io::AsRawHandle as AsRawFd;
 use std::path::Path;
 
+use crate::cancellation::CancellableIterator;
 use crate::compiler::CompilerVariableValue;
 use crate::errors::*;
 use crate::flags::ScanFlags;
 use crate::internals::{
     self, CallbackMsg, CallbackReturn, MemoryBlockIterator, MemoryBlockIteratorSized,
 };
-use crate::rules::{Rule, Rules};
+use crate::options::ScanOptions;
+use crate::outcome::ScanOutcome;
+#[cfg(target_os = "linux")]
+use crate::process::{self, ProcessScanResults};
+#[cfg(feature = "profiling")]
+use crate::profiling::RuleProfilingInfo;
+use crate::reader::ReaderMemoryBlockIterator;
+use crate::rules::{OwnedRule, Rule, Rules};
 
 /// A wrapper around compiled [Rules], with its own set of external variables, flags and timeout.
 ///
@@ -68,6 +79,9 @@ use crate::rules::{Rule, Rules};
 pub struct Scanner<'rules> {
     inner: *mut yara_sys::YR_SCANNER,
     rules: PhantomData<&'rules Rules>,
+    module_data: HashMap<Vec<u8>, Vec<u8>>,
+    reader_block_size: usize,
+    reader_overlap: usize,
 }
 
 // On the subject of thread-safety:
@@ -92,12 +106,15 @@ unsafe impl std::marker::Sync for Scanner<'_> {}
 impl<'a> Scanner<'a> {
     /// Creates a scanner bound to the lifetime of the Rules.
     pub(crate) fn new(rules: &'a Rules) -> Result<Scanner<'a>, YaraError> {
-        // note: The scanner will inherit the external variables currently defined
-        // on the Rules by copying them, but because we provide no way to assign
-        // external variables directly on the Rules, this is not a concern for us.
+        // note: The scanner inherits the external variables currently defined
+        // on the Rules by copying them, so later calls to `Rules::define_variable`
+        // do not affect it.
         Ok(Scanner {
             inner: internals::scanner_create(rules.inner)?,
             rules: PhantomData,
+            module_data: HashMap::new(),
+            reader_block_size: ReaderMemoryBlockIterator::<File>::DEFAULT_BLOCK_SIZE,
+            reader_overlap: ReaderMemoryBlockIterator::<File>::DEFAULT_OVERLAP,
         })
     }
 }
@@ -113,13 +130,18 @@ impl<'rules> Scanner<'rules> {
     /// rest of the scanners.
     ///
     /// Note that the variable must have already been declared with the proper type
-    /// with [define_variable](crate::Compiler::define_variable) when compiling the rules.
+    /// with [define_variable](crate::Compiler::define_variable) when compiling the rules,
+    /// otherwise a [`VariableError`] is returned.
     pub fn define_variable<V: CompilerVariableValue>(
         &mut self,
         identifier: &str,
         value: V,
-    ) -> Result<(), YaraError> {
-        value.assign_in_scanner(self.inner, identifier)
+    ) -> Result<(), Error> {
+        let rules = unsafe { (*self.inner).rules };
+        internals::check_external_variable(rules, identifier, value.variable_type())?;
+        value
+            .assign_in_scanner(self.inner, identifier)
+            .map_err(Into::into)
     }
 
     /// Scan memory.
@@ -143,6 +165,20 @@ impl<'rules> Scanner<'rules> {
         self.scan_mem_callback(mem, callback).map(|_| results)
     }
 
+    /// Scan memory.
+    ///
+    /// Same as [`scan_mem`](Self::scan_mem), but returns owned results which do not
+    /// borrow from the rules. See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `mem` - Slice to scan.
+    /// * `options` - Options of the scan.
+    pub fn scan_mem_owned(&mut self, mem: &[u8], options: &ScanOptions) -> ScanOutcome {
+        ScanOutcome::collect(options, |callback| {
+            self.scan_mem_callback(mem, callback)
+                .map_err(|error| self.scan_error(error))
+        })
+    }
+
     /// Scan memory with custom callback
     ///
     /// Returns
@@ -159,6 +195,7 @@ impl<'rules> Scanner<'rules> {
         mem: &[u8],
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), YaraError> {
+        let callback = self.wrap_callback(callback);
         internals::scanner_scan_mem(self.inner, mem, callback)
     }
 
@@ -182,6 +219,18 @@ impl<'rules> Scanner<'rules> {
         self.scan_file_callback(path, callback).map(|_| results)
     }
 
+    /// Scan a file.
+    ///
+    /// Same as [`scan_file`](Self::scan_file), but returns owned results which do not
+    /// borrow from the rules. See [`ScanOptions`] and [`ScanOutcome`].
+    pub fn scan_file_owned<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        options: &ScanOptions,
+    ) -> ScanOutcome {
+        ScanOutcome::collect(options, |callback| self.scan_file_callback(path, callback))
+    }
+
     /// Scan file with custom callback
     ///
     /// Returns
@@ -201,7 +250,9 @@ impl<'rules> Scanner<'rules> {
         File::open(path)
             .map_err(|e| IoError::new(e, IoErrorKind::OpenScanFile).into())
             .and_then(|file| {
-                internals::scanner_scan_file(self.inner, &file, callback).map_err(|e| e.into())
+                let callback = self.wrap_callback(callback);
+                internals::scanner_scan_file(self.inner, &file, callback)
+                    .map_err(|e| self.scan_error(e))
             })
     }
 
@@ -228,6 +279,50 @@ impl<'rules> Scanner<'rules> {
         self.scan_process_callback(pid, callback).map(|_| results)
     }
 
+    /// Attach a process, pause it, and scan its memory.
+    ///
+    /// Same as [`scan_process`](Self::scan_process), but returns owned results which
+    /// do not borrow from the rules. See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// # Permissions
+    ///
+    /// You need to be able to attach to process `pid`.
+    pub fn scan_process_owned(&mut self, pid: u32, options: &ScanOptions) -> ScanOutcome {
+        ScanOutcome::collect(options, |callback| {
+            self.scan_process_callback(pid, callback)
+                .map_err(|error| self.scan_error(error))
+        })
+    }
+
+    /// Attach a process, pause it, and scan its memory, listing its memory regions to
+    /// find which one each match belongs to.
+    ///
+    /// The regions are read from `/proc/<pid>/maps` at the end of the scan, while the
+    /// process is still paused. See [`ProcessScanResults`].
+    ///
+    /// # Permissions
+    ///
+    /// You need to be able to attach to process `pid`.
+    #[cfg(target_os = "linux")]
+    pub fn scan_process_with_regions(&mut self, pid: u32) -> Result<ProcessScanResults, Error> {
+        let mut rules = Vec::new();
+        let mut regions = None;
+        let callback = |message| {
+            match message {
+                CallbackMsg::RuleMatching(rule) => rules.push(OwnedRule::from(rule)),
+                CallbackMsg::ScanFinished => regions = Some(process::memory_regions(pid)),
+                _ => {}
+            }
+            CallbackReturn::Continue
+        };
+        self.scan_process_callback(pid, callback)?;
+        let regions = match regions {
+            Some(regions) => regions?,
+            None => process::memory_regions(pid)?,
+        };
+        Ok(ProcessScanResults { rules, regions })
+    }
+
     /// Attach a process, pause it, and scan its memory.
     ///
     /// Returns
@@ -248,6 +343,7 @@ impl<'rules> Scanner<'rules> {
         pid: u32,
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), YaraError> {
+        let callback = self.wrap_callback(callback);
         internals::scanner_scan_proc(self.inner, pid, callback)
     }
 
@@ -267,6 +363,17 @@ impl<'rules> Scanner<'rules> {
         self.scan_fd_callback(file, callback).map(|_| results)
     }
 
+    /// Scan a opened file.
+    ///
+    /// Same as [`scan_fd`](Self::scan_fd), but returns owned results which do not
+    /// borrow from the rules. See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `file` - the object that implements get raw file descriptor or file handle
+    /// * `options` - Options of the scan.
+    pub fn scan_fd_owned<F: AsRawFd>(&self, file: &F, options: &ScanOptions) -> ScanOutcome {
+        ScanOutcome::collect(options, |callback| self.scan_fd_callback(file, callback))
+    }
+
     /// Scan a opened file with custom callback
     ///
     /// Returns
@@ -278,7 +385,8 @@ impl<'rules> Scanner<'rules> {
         file: &F,
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), Error> {
-        internals::scanner_scan_file(self.inner, file, callback).map_err(|e| e.into())
+        let callback = self.wrap_callback(callback);
+        internals::scanner_scan_file(self.inner, file, callback).map_err(|e| self.scan_error(e))
     }
 
     /// Scan a series of memory blocks
@@ -301,6 +409,30 @@ impl<'rules> Scanner<'rules> {
             .map(|_| results)
     }
 
+    /// Scan a series of memory blocks
+    ///
+    /// Same as [`scan_mem_blocks`](Self::scan_mem_blocks), but returns owned results
+    /// which do not borrow from the rules. See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// A cancelled scan also stops before the next block.
+    ///
+    /// * `iter` - the iterator over [MemoryBlock](internals::MemoryBlock)
+    /// * `options` - Options of the scan.
+    pub fn scan_mem_blocks_owned(
+        &self,
+        iter: impl MemoryBlockIterator,
+        options: &ScanOptions,
+    ) -> ScanOutcome {
+        let mut iter = CancellableIterator::new(iter, options.token());
+        let mut outcome = ScanOutcome::collect(options, |callback| {
+            self.scan_mem_blocks_callback(&mut iter, callback)
+        });
+        if iter.is_stopped() {
+            outcome.error = Some(Error::Cancelled);
+        }
+        outcome
+    }
+
     /// Scan a series of memory blocks
     ///
     /// Returns
@@ -312,7 +444,9 @@ impl<'rules> Scanner<'rules> {
         iter: impl MemoryBlockIterator,
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), Error> {
-        internals::scanner_scan_mem_blocks(self.inner, iter, callback).map_err(|e| e.into())
+        let callback = self.wrap_callback(callback);
+        internals::scanner_scan_mem_blocks(self.inner, iter, callback)
+            .map_err(|e| self.scan_error(e))
     }
 
     /// Scan a series of memory blocks with size
@@ -335,6 +469,31 @@ impl<'rules> Scanner<'rules> {
             .map(|_| results)
     }
 
+    /// Scan a series of memory blocks with size
+    ///
+    /// Same as [`scan_mem_blocks_sized`](Self::scan_mem_blocks_sized), but returns owned
+    /// results which do not borrow from the rules. See [`ScanOptions`] and
+    /// [`ScanOutcome`].
+    ///
+    /// A cancelled scan also stops before the next block.
+    ///
+    /// * `iter` - the iterator over [MemoryBlock](internals::MemoryBlock) with size
+    /// * `options` - Options of the scan.
+    pub fn scan_mem_blocks_sized_owned(
+        &self,
+        iter: impl MemoryBlockIteratorSized,
+        options: &ScanOptions,
+    ) -> ScanOutcome {
+        let mut iter = CancellableIterator::new(iter, options.token());
+        let mut outcome = ScanOutcome::collect(options, |callback| {
+            self.scan_mem_blocks_sized_callback(&mut iter, callback)
+        });
+        if iter.is_stopped() {
+            outcome.error = Some(Error::Cancelled);
+        }
+        outcome
+    }
+
     /// Scan a series of memory blocks with size
     ///
     /// Returns
@@ -346,7 +505,67 @@ impl<'rules> Scanner<'rules> {
         iter: impl MemoryBlockIteratorSized,
         callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn,
     ) -> Result<(), Error> {
-        internals::scanner_scan_mem_blocks_sized(self.inner, iter, callback).map_err(|e| e.into())
+        let callback = self.wrap_callback(callback);
+        internals::scanner_scan_mem_blocks_sized(self.inner, iter, callback)
+            .map_err(|e| self.scan_error(e))
+    }
+
+    /// Scan a reader, like a decrypted or downloaded stream.
+    ///
+    /// The reader is scanned from its start, in blocks set with
+    /// [`set_reader_block_size`](Self::set_reader_block_size).
+    /// See [`ReaderMemoryBlockIterator`].
+    ///
+    /// * `reader` - the reader to scan
+    pub fn scan_reader<R: Read + Seek>(&self, reader: R) -> Result<Vec<Rule<'rules>>, Error> {
+        let mut results: Vec<Rule> = Vec::new();
+        let callback = |message: CallbackMsg<'rules>| {
+            if let CallbackMsg::RuleMatching(rule) = message {
+                results.push(rule)
+            }
+            CallbackReturn::Continue
+        };
+        self.scan_reader_callback(reader, callback).map(|_| results)
+    }
+
+    /// Scan a reader.
+    ///
+    /// Same as [`scan_reader`](Self::scan_reader), but returns owned results which do not
+    /// borrow from the rules. See [`ScanOptions`] and [`ScanOutcome`].
+    ///
+    /// * `reader` - the reader to scan
+    /// * `options` - Options of the scan.
+    pub fn scan_reader_owned<R: Read + Seek>(
+        &self,
+        reader: R,
+        options: &ScanOptions,
+    ) -> ScanOutcome {
+        let mut iter = self.reader_iterator(reader);
+        let mut outcome = self.scan_mem_blocks_sized_owned(&mut iter, options);
+        if let Some(error) = iter.take_error() {
+            outcome.error = Some(IoError::new(error, IoErrorKind::ReadingScanData).into());
+        }
+        outcome
+    }
+
+    /// Scan a reader with custom callback
+    ///
+    /// An I/O error stops the reading, and is returned once the scan of the data read so far
+    /// is done.
+    ///
+    /// * `reader` - the reader to scan
+    /// * `callback` - YARA callback more read [here](https://yara.readthedocs.io/en/stable/capi.html#scanning-data)
+    pub fn scan_reader_callback<R: Read + Seek>(
+        &self,
+        reader: R,
+        callback: impl FnMut(CallbackMsg<'rules>) -> CallbackReturn,
+    ) -> Result<(), Error> {
+        let mut iter = self.reader_iterator(reader);
+        let result = self.scan_mem_blocks_sized_callback(&mut iter, callback);
+        match iter.take_error() {
+            Some(error) => Err(IoError::new(error, IoErrorKind::ReadingScanData).into()),
+            None => result,
+        }
     }
 
     /// Set the maximum number of seconds that the scanner will spend in any call
@@ -355,10 +574,121 @@ impl<'rules> Scanner<'rules> {
         internals::scanner_set_timeout(self.inner, seconds)
     }
 
+    /// Set the size of the blocks read by [`scan_reader`](Self::scan_reader), and how many
+    /// bytes of a block are read again at the start of the next one.
+    ///
+    /// Strings longer than `overlap` are not found when they span two blocks.
+    /// Defaults to blocks of 1 MiB with an overlap of 4 KiB.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `overlap` is not smaller than `block_size`.
+    pub fn set_reader_block_size(&mut self, block_size: usize, overlap: usize) {
+        assert!(
+            overlap < block_size,
+            "the overlap must be smaller than the block size"
+        );
+        self.reader_block_size = block_size;
+        self.reader_overlap = overlap;
+    }
+
     /// Set the flags that will be used by any call to scan_xxx .
     pub fn set_flags(&mut self, flags: ScanFlags) {
         internals::scanner_set_flags(self.inner, flags.bits())
     }
+
+    /// Set the data passed to a module when the rules import it, like the cuckoo report
+    /// for the `cuckoo` module.
+    ///
+    /// The scanner keeps a copy of `data` and gives it to the module at each scan,
+    /// replacing the data previously set for this module. The callbacks can still
+    /// override it with [`YrModuleImport::set_module_data`](crate::YrModuleImport::set_module_data).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use yara::Compiler;
+    /// let rules = Compiler::new()?
+    ///     .add_rules_str(r#"
+    /// import "cuckoo"
+    ///
+    /// rule contacts_example {
+    ///   condition:
+    ///     cuckoo.network.http_request(/example\.com/)
+    /// }"#)?
+    ///     .compile_rules()?;
+    /// let mut scanner = rules.scanner()?;
+    /// scanner.set_module_data("cuckoo", std::fs::read("report.json").unwrap());
+    /// let results = scanner.scan_file("sample.exe")?;
+    /// # Ok::<(), yara::Error>(())
+    /// ```
+    pub fn set_module_data(&mut self, module_name: &str, data: impl AsRef<[u8]>) {
+        self.module_data
+            .insert(module_name.as_bytes().to_vec(), data.as_ref().to_vec());
+    }
+
+    /// Stop passing data to a module, set with [`set_module_data`](Self::set_module_data).
+    pub fn remove_module_data(&mut self, module_name: &str) {
+        self.module_data.remove(module_name.as_bytes());
+    }
+
+    /// Convert the error of a scan, attaching the rule that caused it.
+    fn scan_error(&self, error: YaraError) -> Error {
+        match error.kind {
+            YaraErrorKind::ScanTimeout | YaraErrorKind::TooManyMatches => ScanError {
+                kind: error.kind,
+                rule: internals::scanner_last_error_rule(self.inner),
+            }
+            .into(),
+            _ => error.into(),
+        }
+    }
+
+    /// Create an iterator over the blocks of `reader`, of the size set with
+    /// [`set_reader_block_size`](Self::set_reader_block_size).
+    fn reader_iterator<R: Read + Seek>(&self, reader: R) -> ReaderMemoryBlockIterator<R> {
+        ReaderMemoryBlockIterator::new(reader)
+            .with_block_size(self.reader_block_size, self.reader_overlap)
+    }
+
+    /// Wrap a callback to attach the module data.
+    fn wrap_callback<'r: 's, 's>(
+        &'s self,
+        mut callback: impl FnMut(CallbackMsg<'r>) -> CallbackReturn + 's,
+    ) -> impl FnMut(CallbackMsg<'r>) -> CallbackReturn + 's {
+        let module_data = &self.module_data;
+        move |mut message| {
+            if let CallbackMsg::ImportModule(import) = &mut message {
+                if let Some(data) = import.name().and_then(|name| module_data.get(name)) {
+                    // Safety: the data is owned by the scanner, which is borrowed for the
+                    // whole scan, so it cannot be modified or dropped before the scan ends.
+                    // The modules only read it.
+                    unsafe { import.set_module_data(data.as_ptr() as *mut c_void, data.len()) };
+                }
+            }
+            callback(message)
+        }
+    }
+
+    /// Get the profiling information of the rules, sorted from the most to the least
+    /// expensive.
+    ///
+    /// The costs accumulate over all the scans made with this scanner, until
+    /// [`reset_profiling_info`](Self::reset_profiling_info) is called.
+    ///
+    /// Requires the `profiling` feature, which builds libyara with profiling enabled.
+    #[cfg(feature = "profiling")]
+    pub fn profiling_info(&self) -> Result<Vec<RuleProfilingInfo<'rules>>, YaraError> {
+        internals::scanner_get_profiling_info(self.inner)
+    }
+
+    /// Reset the profiling information accumulated by this scanner.
+    ///
+    /// Requires the `profiling` feature.
+    #[cfg(feature = "profiling")]
+    pub fn reset_profiling_info(&mut self) {
+        internals::scanner_reset_profiling_info(self.inner)
+    }
 }
 
 #[cfg(test)]
@@ -368,7 +698,9 @@ mod test {
         process::{Command, Stdio},
     };
 
-    use crate::Compiler;
+    use crate::internals::CallbackReturn;
+    use crate::outcome::ScanOutcome;
+    use crate::{CancellationToken, Compiler, Error, ScanOptions, YaraError, YaraErrorKind};
 
     static RULES: &str = r#"rule is_ferris {
         strings:
@@ -439,6 +771,90 @@ mod test {
         assert_eq!(b"Rust", m.data.as_slice());
     }
 
+    #[test]
+    fn partial_keeps_matches_before_error() {
+        let rules = Compiler::new()
+            .unwrap()
+            .add_rules_str(
+                "rule first { condition: true }
+                rule second { condition: true }",
+            )
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let mut scanner = rules.scanner().unwrap();
+        // Fail once the first rule is reported.
+        let outcome = ScanOutcome::collect(&ScanOptions::new(), |callback| {
+            scanner.scan_mem_callback(b"", |message| match callback(message) {
+                CallbackReturn::Continue => CallbackReturn::Error,
+                result => result,
+            })
+        });
+        assert_eq!(1, outcome.matches.len());
+        assert_eq!("first", outcome.matches[0].identifier);
+        assert!(matches!(
+            outcome.error,
+            Some(Error::Yara(YaraError {
+                kind: YaraErrorKind::CallbackError
+            }))
+        ));
+    }
+
+    #[test]
+    fn cancellation_keeps_matches() {
+        let rules = Compiler::new()
+            .unwrap()
+            .add_rules_str(
+                "rule first { condition: true }
+                rule second { condition: true }",
+            )
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let mut scanner = rules.scanner().unwrap();
+        let token = CancellationToken::new();
+        let options = ScanOptions::new().cancellation_token(token.clone());
+        // Cancel from the callback, once the first rule is reported.
+        let outcome = ScanOutcome::collect(&options, |callback| {
+            scanner.scan_mem_callback(b"", |message| {
+                let result = callback(message);
+                token.cancel();
+                result
+            })
+        });
+        assert!(matches!(outcome.error, Some(Error::Cancelled)));
+        assert_eq!(1, outcome.matches.len());
+        assert_eq!("first", outcome.matches[0].identifier);
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn profiling_info() {
+        let rules = Compiler::new()
+            .unwrap()
+            .add_rules_str(
+                r#"rule a { strings: $a = "a" condition: $a }
+                rule b { strings: $b = /b.*c/ condition: $b }"#,
+            )
+            .unwrap()
+            .compile_rules()
+            .unwrap();
+        let mut scanner = rules.scanner().unwrap();
+        scanner.scan_mem(b"abc").unwrap();
+
+        let info = scanner.profiling_info().unwrap();
+        assert_eq!(2, info.len());
+        assert!(info[0].cost >= info[1].cost);
+        let mut identifiers: Vec<_> = info.iter().map(|i| i.identifier).collect();
+        identifiers.sort();
+        assert_eq!(&["a", "b"], identifiers.as_slice());
+        assert!(info.iter().all(|i| i.namespace == "default"));
+
+        scanner.reset_profiling_info();
+        let info = scanner.profiling_info().unwrap();
+        assert!(info.iter().all(|i| i.cost == 0));
+    }
+
     /// A random uuid that should be present in the process memory for the rule
     /// to match.
     static UUID_MATCH: &str = "401d67bf-ff9c-4632-992e-46afed0bbcff";
@@ -0,0 +1,412 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::errors::*;
+use crate::glob::glob_match;
+use crate::rules::{Metadata, MetadataValue, OwnedMetadataValue, Rules, RulesetRule};
+
+/// Selection of the rules of a ruleset by namespace, identifier, tag and metadata.
+///
+/// A rule is selected if it matches every kind of criterion that was set: one of the
+/// namespaces, one of the identifier globs, one of the tags, and all the metadata
+/// predicates. An empty selector selects all the rules.
+///
+/// # Example
+///
+/// ```no_run
+/// # use yara::{Compiler, RuleSelector};
+/// let mut rules = Compiler::new()?
+///     .add_rules_file("rules.yar")?
+///     .compile_rules()?;
+/// let previous = RuleSelector::new()
+///     .namespace("default")
+///     .identifier("APT_*")
+///     .tag("windows")
+///     .metadata("severity >= 5".parse()?)
+///     .apply(&mut rules);
+/// let results = rules.scan_file("sample.exe", 5)?;
+/// previous.restore(&mut rules);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RuleSelector {
+    namespaces: Vec<String>,
+    identifiers: Vec<String>,
+    tags: Vec<String>,
+    metadata: Vec<MetadataPredicate>,
+}
+
+impl RuleSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the rules of a namespace. Can be called several times.
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespaces.push(namespace.to_owned());
+        self
+    }
+
+    /// Select the rules whose identifier matches a glob, like `APT_*`. Can be called several
+    /// times.
+    pub fn identifier(mut self, pattern: &str) -> Self {
+        self.identifiers.push(pattern.to_owned());
+        self
+    }
+
+    /// Select the rules with a tag. Can be called several times, to select the rules with
+    /// any of the tags.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_owned());
+        self
+    }
+
+    /// Select the rules whose metadata satisfy a predicate. Can be called several times, to
+    /// select the rules satisfying all the predicates.
+    pub fn metadata(mut self, predicate: MetadataPredicate) -> Self {
+        self.metadata.push(predicate);
+        self
+    }
+
+    /// Whether a rule is selected.
+    pub fn matches(&self, rule: &RulesetRule) -> bool {
+        (self.namespaces.is_empty() || self.namespaces.iter().any(|ns| ns == rule.namespace))
+            && (self.identifiers.is_empty()
+                || self
+                    .identifiers
+                    .iter()
+                    .any(|pattern| glob_match(pattern, rule.identifier)))
+            && (self.tags.is_empty() || self.tags.iter().any(|tag| rule.tags.contains(&&**tag)))
+            && self
+                .metadata
+                .iter()
+                .all(|predicate| predicate.matches(&rule.metadatas))
+    }
+
+    /// Get the selected rules.
+    pub fn select<'r>(&self, rules: &'r Rules) -> Vec<RulesetRule<'r>> {
+        let mut selected = rules.get_rules();
+        selected.retain(|rule| self.matches(rule));
+        selected
+    }
+
+    /// Enable the selected rules and disable the others.
+    ///
+    /// Returns the previous state of the rules, to restore it after the scans.
+    pub fn apply(&self, rules: &mut Rules) -> RulesEnablement {
+        let previous = RulesEnablement::snapshot(rules);
+        for mut rule in rules.get_rules() {
+            if self.matches(&rule) {
+                rule.enable();
+            } else {
+                rule.disable();
+            }
+        }
+        previous
+    }
+}
+
+/// Which rules of a ruleset are enabled, to restore them later.
+#[derive(Clone, Debug)]
+pub struct RulesEnablement {
+    /// Address of the ruleset, to check the state is restored in the same one.
+    rules: usize,
+    enabled: Vec<bool>,
+}
+
+impl RulesEnablement {
+    /// Save which rules are enabled.
+    pub fn snapshot(rules: &Rules) -> Self {
+        RulesEnablement {
+            rules: rules.inner as usize,
+            enabled: rules
+                .get_rules()
+                .iter()
+                .map(RulesetRule::is_enabled)
+                .collect(),
+        }
+    }
+
+    /// Enable and disable the rules as they were when the snapshot was taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rules` is not the ruleset the snapshot was taken from.
+    pub fn restore(&self, rules: &mut Rules) {
+        assert_eq!(
+            self.rules, rules.inner as usize,
+            "the enablement must be restored in the same rules"
+        );
+        for (mut rule, &enabled) in rules.get_rules().into_iter().zip(&self.enabled) {
+            if enabled {
+                rule.enable();
+            } else {
+                rule.disable();
+            }
+        }
+    }
+}
+
+/// A comparison operator of a [`MetadataPredicate`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetadataComparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl MetadataComparison {
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            MetadataComparison::Equal => ordering == Ordering::Equal,
+            MetadataComparison::NotEqual => ordering != Ordering::Equal,
+            MetadataComparison::Less => ordering == Ordering::Less,
+            MetadataComparison::LessOrEqual => ordering != Ordering::Greater,
+            MetadataComparison::Greater => ordering == Ordering::Greater,
+            MetadataComparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+/// A predicate on a metadata of the rules, like `severity >= 5`.
+///
+/// A rule satisfies the predicate if it has a metadata with this identifier and a value of
+/// the same type that compares as requested. Rules without the metadata, or with a value of
+/// another type, do not satisfy it, even for [`NotEqual`](MetadataComparison::NotEqual).
+///
+/// Strings compare lexicographically, and `false` is less than `true`.
+///
+/// It can be parsed from `[meta.]<identifier> <operator> <value>`, where the operator is one
+/// of `==`, `!=`, `<`, `<=`, `>` and `>=`, and the value is an integer, `true`, `false` or
+/// a double-quoted string, in which `\"` and `\\` are escaped quotes and backslashes:
+///
+/// ```
+/// # use yara::{MetadataComparison, MetadataPredicate, OwnedMetadataValue};
+/// let predicate: MetadataPredicate = r#"meta.author == "x""#.parse()?;
+/// assert_eq!(
+///     predicate,
+///     MetadataPredicate::new("author", MetadataComparison::Equal, OwnedMetadataValue::String("x".to_owned())),
+/// );
+/// # Ok::<(), yara::MetadataPredicateError>(())
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetadataPredicate {
+    pub identifier: String,
+    pub comparison: MetadataComparison,
+    pub value: OwnedMetadataValue,
+}
+
+impl MetadataPredicate {
+    pub fn new(
+        identifier: &str,
+        comparison: MetadataComparison,
+        value: OwnedMetadataValue,
+    ) -> Self {
+        MetadataPredicate {
+            identifier: identifier.to_owned(),
+            comparison,
+            value,
+        }
+    }
+
+    /// Whether metadata satisfy the predicate.
+    pub fn matches(&self, metadatas: &[Metadata]) -> bool {
+        metadatas
+            .iter()
+            .filter(|metadata| metadata.identifier == self.identifier)
+            .filter_map(|metadata| compare(&metadata.value, &self.value))
+            .any(|ordering| self.comparison.matches(ordering))
+    }
+}
+
+fn compare(value: &MetadataValue, other: &OwnedMetadataValue) -> Option<Ordering> {
+    match (value, other) {
+        (MetadataValue::Integer(a), OwnedMetadataValue::Integer(b)) => Some(a.cmp(b)),
+        (MetadataValue::String(a), OwnedMetadataValue::String(b)) => Some((*a).cmp(b.as_str())),
+        (MetadataValue::Boolean(a), OwnedMetadataValue::Boolean(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+impl FromStr for MetadataPredicate {
+    type Err = MetadataPredicateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || MetadataPredicateError(s.to_owned());
+
+        let index = s.find(['=', '!', '<', '>']).ok_or_else(error)?;
+        let identifier = s[..index].trim();
+        let identifier = identifier.strip_prefix("meta.").unwrap_or(identifier);
+        if identifier.is_empty()
+            || !identifier
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(error());
+        }
+
+        // Two-character operators first, as `<` is a prefix of `<=`.
+        let (comparison, value) = [
+            ("==", MetadataComparison::Equal),
+            ("!=", MetadataComparison::NotEqual),
+            ("<=", MetadataComparison::LessOrEqual),
+            (">=", MetadataComparison::GreaterOrEqual),
+            ("<", MetadataComparison::Less),
+            (">", MetadataComparison::Greater),
+        ]
+        .iter()
+        .find_map(|(operator, comparison)| {
+            s[index..]
+                .strip_prefix(operator)
+                .map(|value| (*comparison, value.trim()))
+        })
+        .ok_or_else(error)?;
+
+        let value = match value {
+            "true" => OwnedMetadataValue::Boolean(true),
+            "false" => OwnedMetadataValue::Boolean(false),
+            _ => match value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+            {
+                Some(string) => OwnedMetadataValue::String(unescape(string).ok_or_else(error)?),
+                None => OwnedMetadataValue::Integer(value.parse().map_err(|_| error())?),
+            },
+        };
+
+        Ok(MetadataPredicate {
+            identifier: identifier.to_owned(),
+            comparison,
+            value,
+        })
+    }
+}
+
+/// Unescape the `\"` and `\\` of a string, returning `None` on unescaped quotes.
+fn unescape(string: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(string.len());
+    let mut chars = string.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('"' | '\\')) => unescaped.push(c),
+                Some(c) => {
+                    unescaped.push('\\');
+                    unescaped.push(c);
+                }
+                None => return None,
+            },
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn predicate(s: &str) -> MetadataPredicate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_predicate() {
+        assert_eq!(
+            predicate("meta.severity >= 5"),
+            MetadataPredicate::new(
+                "severity",
+                MetadataComparison::GreaterOrEqual,
+                OwnedMetadataValue::Integer(5)
+            )
+        );
+        assert_eq!(
+            predicate(r#"author=="a \"b\" <c>""#),
+            MetadataPredicate::new(
+                "author",
+                MetadataComparison::Equal,
+                OwnedMetadataValue::String(r#"a "b" <c>"#.to_owned())
+            )
+        );
+        assert_eq!(predicate("score<-1").comparison, MetadataComparison::Less);
+        assert_eq!(predicate("score<-1").value, OwnedMetadataValue::Integer(-1));
+        assert_eq!(
+            predicate("active != false").value,
+            OwnedMetadataValue::Boolean(false)
+        );
+
+        for invalid in [
+            "severity",
+            ">= 5",
+            "meta. >= 5",
+            "sev erity >= 5",
+            "severity => 5",
+            "severity >= high",
+            r#"author == "a"b""#,
+            r#"author == "a\"#,
+        ] {
+            assert!(invalid.parse::<MetadataPredicate>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn predicate_matches() {
+        let metadatas = [
+            Metadata {
+                identifier: "severity",
+                value: MetadataValue::Integer(7),
+            },
+            Metadata {
+                identifier: "author",
+                value: MetadataValue::String("x"),
+            },
+            Metadata {
+                identifier: "author",
+                value: MetadataValue::String("y"),
+            },
+        ];
+        assert!(predicate("severity >= 5").matches(&metadatas));
+        assert!(predicate("severity > 6").matches(&metadatas));
+        assert!(!predicate("severity < 7").matches(&metadatas));
+        assert!(predicate(r#"author == "y""#).matches(&metadatas));
+        assert!(!predicate(r#"author == "z""#).matches(&metadatas));
+        // Missing metadata, or other type.
+        assert!(!predicate("tlp != 1").matches(&metadatas));
+        assert!(!predicate(r#"severity != "high""#).matches(&metadatas));
+    }
+
+    #[test]
+    fn selector_matches() {
+        let rule = RulesetRule {
+            inner: std::ptr::null_mut(),
+            identifier: "APT_Loader",
+            namespace: "windows",
+            metadatas: vec![Metadata {
+                identifier: "severity",
+                value: MetadataValue::Integer(7),
+            }],
+            tags: vec!["apt", "loader"],
+            strings: Vec::new(),
+        };
+        assert!(RuleSelector::new().matches(&rule));
+        assert!(RuleSelector::new()
+            .namespace("linux")
+            .namespace("windows")
+            .identifier("APT_*")
+            .tag("ransomware")
+            .tag("loader")
+            .metadata(predicate("severity >= 5"))
+            .matches(&rule));
+        assert!(!RuleSelector::new().namespace("linux").matches(&rule));
+        assert!(!RuleSelector::new().identifier("*_Dropper").matches(&rule));
+        assert!(!RuleSelector::new().tag("ransomware").matches(&rule));
+        assert!(!RuleSelector::new()
+            .metadata(predicate("severity >= 5"))
+            .metadata(predicate("severity > 8"))
+            .matches(&rule));
+    }
+}
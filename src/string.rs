@@ -11,3 +11,65 @@ pub struct YrString<'a> {
     /// Matches of the string for the scan.
     pub matches: Vec<Match>,
 }
+
+/// An owned version of [`YrString`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedYrString {
+    /// Name of the string, with the '$'.
+    pub identifier: String,
+    /// Matches of the string for the scan.
+    pub matches: Vec<Match>,
+}
+
+impl From<YrString<'_>> for OwnedYrString {
+    fn from(string: YrString<'_>) -> Self {
+        OwnedYrString {
+            identifier: string.identifier.to_owned(),
+            matches: string.matches,
+        }
+    }
+}
+
+/// A string defined in a rule of a ruleset.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct RuleString<'r> {
+    /// Name of the string, with the '$'.
+    pub identifier: &'r str,
+    /// Whether the string is a text, hex or regular expression string.
+    pub kind: RuleStringKind,
+    /// Modifiers of the string.
+    pub modifiers: RuleStringModifiers,
+    /// Offset at which the string must be found, if it is known at compile time
+    /// (e.g. with `$a at 0`).
+    pub fixed_offset: Option<i64>,
+}
+
+/// The kind of a [`RuleString`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RuleStringKind {
+    /// A text string, e.g. `"foo"`.
+    Literal,
+    /// A hex string, e.g. `{ 66 6F 6F }`.
+    Hex,
+    /// A regular expression, e.g. `/fo+/`.
+    Regex,
+}
+
+/// The modifiers of a [`RuleString`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RuleStringModifiers {
+    pub nocase: bool,
+    pub wide: bool,
+    /// Yara sets it on every string that is not `wide`, `base64` or `base64wide`, even
+    /// without the `ascii` modifier.
+    pub ascii: bool,
+    pub xor: bool,
+    pub base64: bool,
+    pub base64wide: bool,
+    pub fullword: bool,
+    pub private: bool,
+}
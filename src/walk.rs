@@ -0,0 +1,469 @@
+//! Parallel scanning of directory trees.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use yara::{Compiler, walk::Walker};
+//! let rules = Compiler::new()?
+//!     .add_rules_str("rule contains_rust { strings: $rust = \"rust\" condition: $rust }")?
+//!     .compile_rules()?;
+//! let walk = Walker::new(rules, ["/home", "/tmp"])
+//!     .workers(4)
+//!     .max_file_size(64 * 1024 * 1024)
+//!     .exclude("**/.git")
+//!     .same_filesystem(true)
+//!     .timeout(10)
+//!     .walk();
+//! for file in walk {
+//!     match file.result {
+//!         Ok(rules) if !rules.is_empty() => println!("{}: {} rules", file.path.display(), rules.len()),
+//!         Ok(_) => {}
+//!         Err(e) => eprintln!("{}: {}", file.path.display(), e),
+//!     }
+//! }
+//! # Ok::<(), yara::Error>(())
+//! ```
+
+use std::collections::HashSet;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::errors::*;
+use crate::flags::ScanFlags;
+use crate::glob::glob_match;
+use crate::options::ScanOptions;
+use crate::rules::{OwnedRule, Rules};
+use crate::scanner::Scanner;
+
+/// What to do with symbolic links found while walking directories.
+///
+/// The roots given to the [`Walker`] are always followed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SymlinkPolicy {
+    /// Ignore symbolic links.
+    #[default]
+    Skip,
+    /// Follow symbolic links to files and directories, visiting each directory once.
+    Follow,
+}
+
+/// The result of the scan of a file.
+///
+/// Also used to report the errors while reading a directory, in which case `path` is
+/// the directory.
+#[derive(Debug)]
+pub struct ScannedFile<T = Vec<OwnedRule>> {
+    pub path: PathBuf,
+    pub result: Result<T, Error>,
+}
+
+/// Builder for a parallel scan of directory trees.
+///
+/// A thread walks the directories and sends the files to scan to `workers` threads,
+/// each reusing its own [`Scanner`].
+pub struct Walker {
+    rules: Arc<Rules>,
+    roots: Vec<PathBuf>,
+    workers: usize,
+    recursive: bool,
+    max_file_size: Option<u64>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    symlinks: SymlinkPolicy,
+    same_filesystem: bool,
+    timeout: i32,
+    flags: ScanFlags,
+}
+
+impl Walker {
+    /// Scan the files under `roots` with `rules`.
+    pub fn new<P: Into<PathBuf>>(
+        rules: impl Into<Arc<Rules>>,
+        roots: impl IntoIterator<Item = P>,
+    ) -> Self {
+        Walker {
+            rules: rules.into(),
+            roots: roots.into_iter().map(Into::into).collect(),
+            workers: 1,
+            recursive: true,
+            max_file_size: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            symlinks: SymlinkPolicy::default(),
+            same_filesystem: false,
+            timeout: 0,
+            flags: ScanFlags::default(),
+        }
+    }
+
+    /// Number of threads scanning the files. Defaults to 1.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Descend in the subdirectories of the roots. Defaults to true.
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Skip the files larger than `size` bytes.
+    pub fn max_file_size(mut self, size: u64) -> Self {
+        self.max_file_size = Some(size);
+        self
+    }
+
+    /// Only scan the files matching this glob. Can be called several times.
+    ///
+    /// Patterns containing a `/` are matched against the path relative to the root,
+    /// the others against the file name. See [`exclude`](Self::exclude) for the syntax.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Skip the files and directories matching this glob. Can be called several times.
+    ///
+    /// `?` matches any character, `*` any sequence of characters except `/`, and
+    /// `**` any sequence of characters. Patterns containing a `/` are matched against
+    /// the path relative to the root, with `/` as separator, the others against the
+    /// file name.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// What to do with symbolic links. Defaults to [`SymlinkPolicy::Skip`].
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Do not descend in directories on other filesystems than their root.
+    ///
+    /// Only supported on Unix, ignored elsewhere.
+    pub fn same_filesystem(mut self, same_filesystem: bool) -> Self {
+        self.same_filesystem = same_filesystem;
+        self
+    }
+
+    /// Timeout of the scan of each file, in seconds.
+    pub fn timeout(mut self, seconds: i32) -> Self {
+        self.timeout = seconds;
+        self
+    }
+
+    /// Flags of the scan of each file.
+    pub fn flags(mut self, flags: ScanFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Start the scan.
+    ///
+    /// Returns an iterator over the scanned files, in no particular order.
+    /// Dropping it stops the scan once the files being scanned are done.
+    pub fn walk(self) -> Walk {
+        self.walk_with(|scanner, path| {
+            scanner
+                .scan_file_owned(path, &ScanOptions::new())
+                .into_result()
+        })
+    }
+
+    /// Start the scan, scanning each file with `scan`.
+    ///
+    /// Useful to handle the [`CallbackMsg`](crate::CallbackMsg) yourself, for example
+    /// with [`Scanner::scan_file_callback`]. The scanners already have the timeout
+    /// and flags of the walker set.
+    pub fn walk_with<T, F>(self, scan: F) -> Walk<T>
+    where
+        T: Send + 'static,
+        F: Fn(&mut Scanner, &Path) -> Result<T, Error> + Send + Sync + 'static,
+    {
+        let scan = Arc::new(scan);
+        let stop = Arc::new(AtomicBool::new(false));
+        let (results_tx, results) = mpsc::channel();
+        let (paths_tx, paths_rx) = mpsc::sync_channel(self.workers * 2);
+        let paths_rx = Arc::new(Mutex::new(paths_rx));
+
+        let mut threads: Vec<JoinHandle<()>> = (0..self.workers)
+            .map(|_| {
+                let rules = self.rules.clone();
+                let paths = paths_rx.clone();
+                let results = results_tx.clone();
+                let stop = stop.clone();
+                let scan = scan.clone();
+                let (timeout, flags) = (self.timeout, self.flags);
+                thread::spawn(move || {
+                    scan_files(&rules, timeout, flags, &*scan, &paths, &results, &stop)
+                })
+            })
+            .collect();
+
+        let walker_stop = stop.clone();
+        threads.push(thread::spawn(move || {
+            let mut walker = DirWalker {
+                config: &self,
+                paths: paths_tx,
+                results: results_tx,
+                stop: walker_stop,
+                visited: HashSet::new(),
+            };
+            walker.walk_roots();
+        }));
+
+        Walk {
+            results,
+            stop,
+            threads,
+        }
+    }
+}
+
+/// Iterator over the files scanned by a [`Walker`].
+pub struct Walk<T = Vec<OwnedRule>> {
+    results: Receiver<ScannedFile<T>>,
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl<T> Iterator for Walk<T> {
+    type Item = ScannedFile<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.results.recv() {
+            Ok(file) => Some(file),
+            Err(_) => {
+                // Every thread is done, propagate their panics.
+                for thread in self.threads.drain(..) {
+                    if let Err(panic) = thread.join() {
+                        std::panic::resume_unwind(panic);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<T> Drop for Walk<T> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn scan_files<T>(
+    rules: &Rules,
+    timeout: i32,
+    flags: ScanFlags,
+    scan: &dyn Fn(&mut Scanner, &Path) -> Result<T, Error>,
+    paths: &Mutex<Receiver<PathBuf>>,
+    results: &Sender<ScannedFile<T>>,
+    stop: &AtomicBool,
+) {
+    let mut scanner = rules.scanner().map(|mut scanner| {
+        scanner.set_timeout(timeout);
+        scanner.set_flags(flags);
+        scanner
+    });
+
+    while !stop.load(Ordering::Relaxed) {
+        // The lock is released as soon as a path is received.
+        let path = match paths.lock().unwrap().recv() {
+            Ok(path) => path,
+            Err(_) => return,
+        };
+        let result = match &mut scanner {
+            Ok(scanner) => scan(scanner, &path),
+            Err(e) => Err((*e).into()),
+        };
+        if results.send(ScannedFile { path, result }).is_err() {
+            return;
+        }
+    }
+}
+
+struct DirWalker<'a, T> {
+    config: &'a Walker,
+    paths: SyncSender<PathBuf>,
+    results: Sender<ScannedFile<T>>,
+    stop: Arc<AtomicBool>,
+    // Canonical paths of the directories already visited, when following symlinks.
+    visited: HashSet<PathBuf>,
+}
+
+impl<T> DirWalker<'_, T> {
+    fn walk_roots(&mut self) {
+        for root in &self.config.roots {
+            let metadata = match fs::metadata(root) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    self.report_error(root.clone(), e, IoErrorKind::OpeningScanRoot);
+                    continue;
+                }
+            };
+            let file_name = root
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let keep_going = if metadata.is_dir() {
+                self.walk_dir(root, device(&metadata))
+            } else {
+                self.visit_file(root.clone(), &file_name, &file_name, &metadata)
+            };
+            if !keep_going {
+                return;
+            }
+        }
+    }
+
+    /// Returns false if the scan is stopped.
+    fn walk_dir(&mut self, root: &Path, root_device: Option<u64>) -> bool {
+        let mut dirs = vec![root.to_path_buf()];
+        if self.config.symlinks == SymlinkPolicy::Follow {
+            if let Ok(canonical) = root.canonicalize() {
+                self.visited.insert(canonical);
+            }
+        }
+
+        while let Some(dir) = dirs.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    if !self.report_error(dir, e, IoErrorKind::ReadingDirectory) {
+                        return false;
+                    }
+                    continue;
+                }
+            };
+
+            for entry in entries {
+                if self.stop.load(Ordering::Relaxed) {
+                    return false;
+                }
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        if !self.report_error(dir.clone(), e, IoErrorKind::ReadingDirectory) {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+                let path = entry.path();
+                let metadata = match entry.file_type() {
+                    Ok(file_type) if file_type.is_symlink() => match self.config.symlinks {
+                        SymlinkPolicy::Skip => continue,
+                        SymlinkPolicy::Follow => fs::metadata(&path),
+                    },
+                    Ok(_) => entry.metadata(),
+                    Err(e) => Err(e),
+                };
+                let metadata = match metadata {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        if !self.report_error(path, e, IoErrorKind::ReadingDirectory) {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+
+                let relative = relative_path(root, &path);
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                if metadata.is_dir() {
+                    if !self.config.recursive || self.is_excluded(&relative, &file_name) {
+                        continue;
+                    }
+                    if self.config.same_filesystem && device(&metadata) != root_device {
+                        continue;
+                    }
+                    if self.config.symlinks == SymlinkPolicy::Follow {
+                        if let Ok(canonical) = path.canonicalize() {
+                            if !self.visited.insert(canonical) {
+                                continue;
+                            }
+                        }
+                    }
+                    dirs.push(path);
+                } else if metadata.is_file()
+                    && !self.visit_file(path, &relative, &file_name, &metadata)
+                {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Returns false if the scan is stopped.
+    fn visit_file(
+        &self,
+        path: PathBuf,
+        relative: &str,
+        file_name: &str,
+        metadata: &Metadata,
+    ) -> bool {
+        let included = self.config.include.is_empty()
+            || matches_any(&self.config.include, relative, file_name);
+        let too_large = self
+            .config
+            .max_file_size
+            .map_or(false, |max| metadata.len() > max);
+        if !included || too_large || self.is_excluded(relative, file_name) {
+            return true;
+        }
+
+        self.paths.send(path).is_ok()
+    }
+
+    fn is_excluded(&self, relative: &str, file_name: &str) -> bool {
+        matches_any(&self.config.exclude, relative, file_name)
+    }
+
+    /// Returns false if the scan is stopped.
+    fn report_error(&self, path: PathBuf, error: std::io::Error, kind: IoErrorKind) -> bool {
+        let result = Err(IoError::new(error, kind).into());
+        self.results.send(ScannedFile { path, result }).is_ok()
+    }
+}
+
+fn matches_any(patterns: &[String], relative: &str, file_name: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        if pattern.contains('/') {
+            glob_match(pattern, relative)
+        } else {
+            glob_match(pattern, file_name)
+        }
+    })
+}
+
+/// The path relative to the root, with `/` as separator.
+fn relative_path(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(unix)]
+fn device(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_metadata: &Metadata) -> Option<u64> {
+    None
+}
@@ -0,0 +1,114 @@
+//! Compare the output of `yara-rs` with the output of the C `yara` tool.
+#![cfg(feature = "cli")]
+
+use std::path::Path;
+use std::process::Command;
+
+const RULES: &str = r#"
+import "tests"
+
+rule escaped : tag {
+  meta:
+    author = "a \"quoted\" \\ name"
+    version = 2
+    stable = true
+  strings:
+    $text = "I love"
+    $hex = { 52 75 73 74 21 0A }
+  condition:
+    all of them and tests.constants.one == 1
+}
+
+rule not_matching {
+  condition:
+    false
+}
+"#;
+
+/// Run `yara-rs` with `args`, then the rules file and `target`.
+fn yara_rs(args: &[&str], target: &Path) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let rules = dir.path().join("rules.yar");
+    std::fs::write(&rules, RULES).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_yara-rs"))
+        .args(args)
+        .arg(&rules)
+        .arg(target)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn scanfile() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scanfile.txt"))
+}
+
+#[test]
+fn test_print_strings() {
+    let path = scanfile().display();
+    assert_eq!(
+        yara_rs(&["-s"], scanfile()),
+        format!("escaped {path}\n0x0:$text: I love\n0x7:$hex: 52 75 73 74 21 0A\n")
+    );
+}
+
+#[test]
+fn test_print_meta() {
+    let path = scanfile().display();
+    assert_eq!(
+        yara_rs(&["-m"], scanfile()),
+        format!("escaped [author=\"a \\\"quoted\\\" \\\\ name\",version=2,stable=true] {path}\n")
+    );
+}
+
+#[test]
+fn test_count() {
+    let path = scanfile().display();
+    assert_eq!(yara_rs(&["-c"], scanfile()), format!("{path}: 1\n"));
+    assert_eq!(yara_rs(&["-n", "-c"], scanfile()), format!("{path}: 1\n"));
+}
+
+#[test]
+fn test_print_module_data() {
+    let path = scanfile().display();
+    let output = yara_rs(&["-D"], scanfile());
+    assert!(
+        output.starts_with("tests\n\tconstants\n\t\tone = 1\n\t\ttwo = 2\n\t\tfoo = \"foo\"\n"),
+        "{output}"
+    );
+    assert!(output.ends_with(&format!("\nescaped {path}\n")), "{output}");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recursive_symlinks() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    let linked = dir.path().join("linked");
+    std::fs::create_dir(&root).unwrap();
+    std::fs::create_dir(&linked).unwrap();
+    std::fs::copy(scanfile(), root.join("a.txt")).unwrap();
+    std::fs::copy(scanfile(), linked.join("b.txt")).unwrap();
+    std::os::unix::fs::symlink(&linked, root.join("link")).unwrap();
+
+    // Like the C tool, symlinks are followed unless `-N` is given.
+    let mut output: Vec<_> = yara_rs(&["-r"], &root).lines().map(String::from).collect();
+    output.sort();
+    assert_eq!(
+        output,
+        [
+            format!("escaped {}", root.join("a.txt").display()),
+            format!("escaped {}", root.join("link").join("b.txt").display()),
+        ]
+    );
+    assert_eq!(
+        yara_rs(&["-r", "-N"], &root),
+        format!("escaped {}\n", root.join("a.txt").display())
+    );
+}
@@ -0,0 +1,109 @@
+//! End-to-end test of a module written in Rust.
+//!
+//! libyara must be built with the module in its modules table, so this test is ignored
+//! by default. Run it with:
+//!
+//! ```text
+//! YARA_RUST_MODULES=rust_test cargo test --features vendored --test module -- --include-ignored
+//! ```
+
+use yara::module::{Declarations, FunctionContext, Module, ModuleObject, ReturnValue, ScanData};
+use yara::{Compiler, YaraError};
+
+struct RustTest;
+
+impl Module for RustTest {
+    fn declarations(module: &mut Declarations) -> Result<(), YaraError> {
+        module.integer("first_byte")?;
+        module.structure("data", |data| data.string("name"))?;
+        module.function("add", "ii", "i", |ctx: &FunctionContext| {
+            match (ctx.integer(0), ctx.integer(1)) {
+                (Some(a), Some(b)) => ReturnValue::Integer(a.wrapping_add(b)),
+                _ => ReturnValue::Undefined,
+            }
+        })?;
+        module.function("add", "ff", "f", |ctx: &FunctionContext| {
+            match (ctx.float(0), ctx.float(1)) {
+                (Some(a), Some(b)) => ReturnValue::Float(a + b),
+                _ => ReturnValue::Undefined,
+            }
+        })?;
+        module.function("length", "s", "i", |ctx: &FunctionContext| {
+            ctx.string(0).map_or(ReturnValue::Undefined, |s| {
+                ReturnValue::Integer(s.len() as i64)
+            })
+        })
+    }
+
+    fn load(
+        module: &mut ModuleObject,
+        scan_data: &mut ScanData,
+        module_data: Option<&[u8]>,
+    ) -> Result<(), YaraError> {
+        let first_byte = scan_data
+            .first_block()
+            .and_then(|block| block.data().first().copied());
+        if let Some(byte) = first_byte {
+            module.set_integer("first_byte", i64::from(byte))?;
+        }
+        if let Some(name) = module_data {
+            module.set_string("data.name", name)?;
+        }
+        Ok(())
+    }
+}
+
+yara::declare_module!(rust_test, RustTest);
+
+const RULES: &str = r#"
+import "rust_test"
+
+rule first_byte {
+  condition:
+    rust_test.first_byte == 0x52
+}
+
+rule add {
+  condition:
+    rust_test.add(1, 2) == 3 and rust_test.add(1.5, 1.0) == 2.5
+}
+
+rule length {
+  condition:
+    rust_test.length("rust") == 4
+}
+
+rule name {
+  condition:
+    rust_test.data.name == "demo"
+}
+"#;
+
+#[test]
+#[ignore = "needs yara-sys built with YARA_RUST_MODULES=rust_test"]
+fn test_rust_module() {
+    let rules = Compiler::new()
+        .unwrap()
+        .add_rules_str(RULES)
+        .expect("should add the rules")
+        .compile_rules()
+        .expect("should compile");
+    let mut scanner = rules.scanner().unwrap();
+
+    let matches: Vec<String> = scanner
+        .scan_mem(b"Rust")
+        .expect("should scan")
+        .iter()
+        .map(|rule| rule.identifier.to_string())
+        .collect();
+    assert_eq!(matches, ["first_byte", "add", "length"]);
+
+    scanner.set_module_data("rust_test", "demo");
+    let matches: Vec<String> = scanner
+        .scan_mem(b"")
+        .expect("should scan")
+        .iter()
+        .map(|rule| rule.identifier.to_string())
+        .collect();
+    assert_eq!(matches, ["add", "length", "name"]);
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
+use std::ffi::CStr;
 
 use yara::{
-    CallbackMsg, CallbackReturn, CompileErrorLevel, Compiler, Error, MemoryBlock,
-    MemoryBlockIterator, MemoryBlockIteratorSized, Metadata, MetadataValue, Rules, ScanFlags, Yara,
-    YrObjectValue,
+    CallbackMsg, CallbackReturn, CancellationToken, CompileErrorLevel, Compiler, Error,
+    ExternalVariable, ExternalVariableType, ExternalVariableValue, IoErrorKind, MemoryBlock,
+    MemoryBlockIterator, MemoryBlockIteratorSized, Metadata, MetadataValue, OwnedMetadata,
+    OwnedMetadataValue, OwnedRule, OwnedYrObjectValue, RuleStringKind, Rules, ScanError, ScanFlags,
+    ScanOptions, VariableError, Yara, YaraError, YrObjectValue,
 };
 
 const RULES: &str = r#"
@@ -138,6 +141,62 @@ fn test_scan_mem() {
     }
 }
 
+#[test]
+fn test_scan_mem_owned() {
+    let results = {
+        let rules = get_default_rules();
+        rules
+            .scan_mem_owned(
+                "I love Rust! And go is ok".as_bytes(),
+                10,
+                &ScanOptions::new(),
+            )
+            .into_result()
+            .expect("Should be Ok")
+    };
+
+    // The results outlive the rules, and can be moved to another thread.
+    let results = std::thread::spawn(move || results).join().unwrap();
+    assert_eq!(3, results.len());
+    let rule = &results[0];
+    assert_eq!("is_awesome", rule.identifier);
+    assert_eq!("default", rule.namespace);
+    assert_eq!(1, rule.strings.len());
+    assert_eq!("$rust", rule.strings[0].identifier);
+    assert_eq!(7, rule.strings[0].matches[0].offset);
+    assert_eq!(b"Rust", rule.strings[0].matches[0].data.as_slice());
+}
+
+#[test]
+fn test_owned_rule() {
+    let rules = compile(
+        r#"
+rule contains_abc: tag {
+  meta:
+    a_string = "value"
+  strings:
+    $abc = "abc"
+  condition:
+    $abc
+}
+"#,
+    );
+    let mut matches = rules.scan_mem(b"abc", 10).expect("should have scanned");
+    let string_matches = matches[0].strings[0].matches.clone();
+    let owned = OwnedRule::from(matches.remove(0));
+
+    assert_eq!("contains_abc", owned.identifier);
+    assert_eq!(vec!["tag".to_string()], owned.tags);
+    assert_eq!(
+        OwnedMetadata {
+            identifier: "a_string".to_string(),
+            value: OwnedMetadataValue::String("value".to_string())
+        },
+        owned.metadatas[0]
+    );
+    assert_eq!(string_matches, owned.strings[0].matches);
+}
+
 #[test]
 fn test_scan_mem_callback_abort() {
     let rules = get_default_rules();
@@ -163,6 +222,130 @@ fn test_scan_mem_callback_error() {
     assert_eq!(yara_sys::Error::CallbackError, error.kind);
 }
 
+#[test]
+fn test_scanner_error_rule() {
+    let rules = compile(
+        r#"rule too_many {
+  strings:
+    $a = "a"
+  condition:
+    $a
+}"#,
+    );
+    let filename = "_too_many_matches.txt";
+    std::fs::write(filename, vec![b'a'; 1_100_000]).expect("should have written the file");
+
+    let mut scanner = rules.scanner().unwrap();
+    let callback = |message| match message {
+        CallbackMsg::TooManyMatches(_) => CallbackReturn::Error,
+        _ => CallbackReturn::Continue,
+    };
+    let error = scanner
+        .scan_file_callback(filename, callback)
+        .expect_err("Should be Err");
+    std::fs::remove_file(filename).ok();
+    let rule = match error {
+        Error::Scan(ScanError {
+            kind: yara_sys::Error::TooManyMatches,
+            rule: Some(rule),
+        }) => rule,
+        error => panic!("Should have the rule: {error:?}"),
+    };
+    assert_eq!("default", rule.namespace);
+    assert_eq!("too_many", rule.identifier);
+    assert_eq!(Some("$a"), rule.string.as_deref());
+
+    // The other errors have no rule.
+    let callback = |_| CallbackReturn::Error;
+    let error = scanner
+        .scan_file_callback("tests/scanfile.txt", callback)
+        .expect_err("Should be Err");
+    assert!(matches!(
+        error,
+        Error::Yara(YaraError {
+            kind: yara_sys::Error::CallbackError
+        })
+    ));
+}
+
+#[test]
+fn test_cancellation() {
+    let rules = get_default_rules();
+    let mut scanner = rules.scanner().unwrap();
+
+    let token = CancellationToken::new();
+    let options = ScanOptions::new().cancellation_token(token.clone());
+    let outcome = scanner.scan_mem_owned(b"rust ok", &options);
+    assert!(outcome.is_complete());
+    assert_eq!(2, outcome.matches.len());
+    // The following scans with a cancelled token are cancelled immediately.
+    token.cancel();
+    assert!(matches!(
+        scanner.scan_mem_owned(b"rust ok", &options).error,
+        Some(Error::Cancelled)
+    ));
+    assert!(matches!(
+        rules.scan_mem_owned(b"rust ok", 10, &options).error,
+        Some(Error::Cancelled)
+    ));
+
+    // The other scans are not affected.
+    assert_eq!(2, scanner.scan_mem(b"rust ok").unwrap().len());
+    let options = ScanOptions::new().cancellation_token(CancellationToken::new());
+    assert!(scanner.scan_mem_owned(b"rust ok", &options).is_complete());
+}
+
+#[test]
+fn test_scanner_cancellation_between_blocks() {
+    struct CancellingIter<'a> {
+        blocks: Vec<&'a [u8]>,
+        index: usize,
+        token: CancellationToken,
+    }
+
+    impl MemoryBlockIterator for CancellingIter<'_> {
+        fn first(&mut self) -> Option<MemoryBlock<'_>> {
+            self.index = 0;
+            self.next()
+        }
+
+        fn next(&mut self) -> Option<MemoryBlock<'_>> {
+            let data = self.blocks.get(self.index)?;
+            self.index += 1;
+            // Cancel the scan after the first block.
+            self.token.cancel();
+            Some(MemoryBlock::new(self.index as u64 * 100, data))
+        }
+    }
+
+    let rules = get_default_rules();
+    let scanner = rules.scanner().unwrap();
+    let token = CancellationToken::new();
+    let mut iter = CancellingIter {
+        blocks: vec![b"rust", b"ok", b"go"],
+        index: 0,
+        token: token.clone(),
+    };
+    let options = ScanOptions::new().cancellation_token(token);
+    let outcome = scanner.scan_mem_blocks_owned(&mut iter, &options);
+    assert!(matches!(outcome.error, Some(Error::Cancelled)));
+    assert_eq!(1, iter.index);
+}
+
+#[test]
+fn test_scan_outcome() {
+    let rules = get_default_rules();
+    let outcome = rules.scan_mem_owned(b"rust ok", 10, &ScanOptions::new());
+    assert!(outcome.is_complete());
+    assert_eq!(2, outcome.matches.len());
+    assert_eq!(2, outcome.into_result().unwrap().len());
+
+    let outcome = rules.scan_file_owned("tests/non_existing_file.txt", 10, &ScanOptions::new());
+    assert!(!outcome.is_complete());
+    assert!(outcome.matches.is_empty());
+    assert!(matches!(outcome.error, Some(Error::Io(_))));
+}
+
 #[test]
 fn test_scan_file() {
     let rules = get_default_rules();
@@ -180,6 +363,60 @@ fn test_scan_fd() {
     assert_eq!(1, result.len());
 }
 
+#[cfg(feature = "tokio")]
+#[test]
+fn test_async_rules() {
+    use std::time::Duration;
+
+    let rules = yara::AsyncRules::new(get_default_rules(), 2);
+    let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+    let (mem, file, fd) = runtime.block_on(async {
+        let fd = std::fs::File::open("tests/scanfile.txt").unwrap();
+        tokio::join!(
+            rules.scan_mem("rust ok".into(), Duration::from_secs(10)),
+            rules.scan_file("tests/scanfile.txt".into(), Duration::from_secs(10)),
+            rules.scan_fd(fd, Duration::from_secs(10)),
+        )
+    });
+    assert_eq!(2, mem.unwrap().len());
+    assert_eq!(1, file.unwrap().len());
+    assert_eq!(1, fd.unwrap().len());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_async_rules_drop() {
+    use std::time::Duration;
+
+    let rules = compile("rule slow { strings: $a = /a.*a.*b/ condition: $a }");
+    let rules = yara::AsyncRules::new(rules, 1);
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .max_blocking_threads(1)
+        .enable_time()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        // Keep the only blocking thread busy, so the scans wait to start.
+        let (open_gate, gate) = std::sync::mpsc::channel::<()>();
+        let gate = tokio::task::spawn_blocking(move || gate.recv());
+
+        // Takes minutes if not skipped.
+        let slow = rules.scan_mem(vec![b'a'; 10_000_000].into(), Duration::ZERO);
+        assert!(tokio::time::timeout(Duration::from_millis(100), slow)
+            .await
+            .is_err());
+        open_gate.send(()).unwrap();
+        gate.await.unwrap().unwrap();
+
+        // The dropped scan is skipped, releasing its permit.
+        let fast = rules.scan_mem("aab".into(), Duration::ZERO);
+        let results = tokio::time::timeout(Duration::from_secs(10), fast)
+            .await
+            .expect("the permit should be released");
+        assert_eq!(1, results.unwrap().len());
+    });
+}
+
 #[test]
 fn test_scan_mem_blocks() {
     struct TestIter<'a> {
@@ -258,6 +495,64 @@ fn test_scan_mem_blocks_sized() {
     assert_eq!(1, result.len());
 }
 
+#[test]
+fn test_scan_reader() {
+    let rules = compile(
+        r#"
+rule spanning {
+  strings:
+    $a = "boundary"
+  condition:
+    $a at 26 and filesize == 64
+}"#,
+    );
+    let mut data = vec![b'.'; 64];
+    data[26..34].copy_from_slice(b"boundary");
+
+    let mut scanner = rules.scanner().unwrap();
+    scanner.set_reader_block_size(32, 8);
+    let result = scanner
+        .scan_reader(std::io::Cursor::new(&data))
+        .expect("Should be ok");
+    assert_eq!(1, result.len());
+    let m = &result[0].strings[0].matches;
+    assert_eq!(1, m.len());
+    assert_eq!(26, m[0].offset + m[0].base);
+
+    scanner.set_reader_block_size(32, 4);
+    let result = scanner
+        .scan_reader_owned(std::io::Cursor::new(&data), &ScanOptions::new())
+        .into_result()
+        .expect("Should be ok");
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_scan_reader_overlap() {
+    let rules = compile(
+        r#"
+rule in_overlap {
+  strings:
+    $a = "rust"
+  condition:
+    #a == 1 and !a[1] == 4 and @a[1] == 26
+}"#,
+    );
+    let mut data = vec![b'.'; 64];
+    data[26..30].copy_from_slice(b"rust");
+
+    // The blocks are [0, 32), [24, 56) and [48, 64): the string is in the first two.
+    let mut scanner = rules.scanner().unwrap();
+    scanner.set_reader_block_size(32, 8);
+    let result = scanner
+        .scan_reader(std::io::Cursor::new(&data))
+        .expect("Should be ok");
+    assert_eq!(1, result.len());
+    let m = &result[0].strings[0].matches;
+    assert_eq!(1, m.len());
+    assert_eq!(26, m[0].offset + m[0].base);
+}
+
 #[test]
 fn test_scan_mem_console_log() {
     let rule = r#"
@@ -416,6 +711,61 @@ rule contains_abc {
     );
 }
 
+#[test]
+fn test_ruleset_rule_strings() {
+    let rules = compile(
+        r#"
+global rule is_global {
+  condition:
+    true
+}
+
+private rule is_private {
+  strings:
+    $text = "foo" nocase wide ascii fullword
+    $hex = { 66 6F [2-4] 6F }
+    $re = /fo+/
+    $xor = "bar" xor private
+    $b64 = "baz" base64
+  condition:
+    $text at 0 or any of ($hex, $re, $xor, $b64)
+}
+"#,
+    );
+    let ruleset_rules = rules.get_rules();
+    assert_eq!(2, ruleset_rules.len());
+
+    let global = &ruleset_rules[0];
+    assert!(global.is_global());
+    assert!(!global.is_private());
+    assert!(!global.is_null());
+    assert!(global.strings.is_empty());
+
+    let private = &ruleset_rules[1];
+    assert!(private.is_private());
+    assert!(!private.is_global());
+    let identifiers: Vec<_> = private.strings.iter().map(|s| s.identifier).collect();
+    assert_eq!(vec!["$text", "$hex", "$re", "$xor", "$b64"], identifiers);
+
+    let text = &private.strings[0];
+    assert_eq!(RuleStringKind::Literal, text.kind);
+    assert!(text.modifiers.nocase);
+    assert!(text.modifiers.wide);
+    assert!(text.modifiers.ascii);
+    assert!(text.modifiers.fullword);
+    assert!(!text.modifiers.xor);
+    assert_eq!(Some(0), text.fixed_offset);
+
+    assert_eq!(RuleStringKind::Hex, private.strings[1].kind);
+    assert_eq!(RuleStringKind::Regex, private.strings[2].kind);
+    assert_eq!(None, private.strings[2].fixed_offset);
+    // Set implicitly on the strings that are not wide.
+    assert!(private.strings[2].modifiers.ascii);
+    assert!(private.strings[3].modifiers.xor);
+    assert!(private.strings[3].modifiers.private);
+    assert!(private.strings[4].modifiers.base64);
+}
+
 #[test]
 fn test_external_variables() {
     let rule_definition = "
@@ -439,6 +789,105 @@ rule IsNCharLong {
     assert_eq!(1, result.len());
 }
 
+#[test]
+fn test_rules_external_variables() {
+    let mut compiler = Compiler::new().unwrap();
+    compiler.define_variable("habitat", "land").unwrap();
+    compiler.define_variable("life_expectancy", 99).unwrap();
+    compiler.define_variable("size", 1.5_f64).unwrap();
+    compiler.define_variable("is_cute", true).unwrap();
+    let rules = compiler.compile_rules().unwrap();
+
+    let variables = rules.external_variables();
+    assert_eq!(
+        vec![
+            ExternalVariable {
+                identifier: "habitat",
+                value: ExternalVariableValue::String(CStr::from_bytes_with_nul(b"land\0").unwrap()),
+            },
+            ExternalVariable {
+                identifier: "life_expectancy",
+                value: ExternalVariableValue::Integer(99),
+            },
+            ExternalVariable {
+                identifier: "size",
+                value: ExternalVariableValue::Float(1.5),
+            },
+            ExternalVariable {
+                identifier: "is_cute",
+                value: ExternalVariableValue::Boolean(true),
+            },
+        ],
+        variables
+    );
+
+    let mut scanner = rules.scanner().unwrap();
+    match scanner.define_variable("habitat", 5) {
+        Err(Error::Variable(VariableError::TypeMismatch {
+            identifier,
+            expected,
+            actual,
+        })) => {
+            assert_eq!("habitat", identifier);
+            assert_eq!(ExternalVariableType::String, expected);
+            assert_eq!(ExternalVariableType::Integer, actual);
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    match scanner.define_variable("color", "red") {
+        Err(Error::Variable(VariableError::Undefined(identifier))) => {
+            assert_eq!("color", identifier);
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    scanner.define_variable("habitat", "ocean").unwrap();
+}
+
+#[test]
+fn test_rules_define_variable() {
+    let mut compiler = Compiler::new().unwrap();
+    compiler.define_variable("desired_length", 5).unwrap();
+    let mut rules = compiler
+        .add_rules_str("rule IsNCharLong { condition: filesize == desired_length }")
+        .unwrap()
+        .compile_rules()
+        .unwrap();
+
+    let mut saved_rules = Vec::new();
+    rules.save_to_stream(&mut saved_rules).expect("Should save");
+    let mut rules = Rules::load_from_stream(&saved_rules[..]).expect("Should load");
+    assert_eq!(1, rules.scan_mem(b"abcde", 10).unwrap().len());
+
+    rules.define_variable("desired_length", 3).unwrap();
+    assert_eq!(0, rules.scan_mem(b"abcde", 10).unwrap().len());
+    assert_eq!(1, rules.scan_mem(b"abc", 10).unwrap().len());
+    assert_eq!(
+        ExternalVariableValue::Integer(3),
+        rules.external_variables()[0].value
+    );
+    assert_eq!(1, rules.scanner().unwrap().scan_mem(b"abc").unwrap().len());
+
+    assert!(matches!(
+        rules.define_variable("desired_length", "3"),
+        Err(Error::Variable(VariableError::TypeMismatch { .. }))
+    ));
+}
+
+#[test]
+fn test_rules_stats() {
+    let rules = get_default_rules();
+    let stats = rules.stats().unwrap();
+    assert_eq!(3, stats.num_rules);
+    assert_eq!(3, stats.num_strings);
+    assert!(stats.ac_matches > 0);
+    assert_eq!(100, stats.top_ac_match_list_lengths.len());
+    assert_eq!(101, stats.ac_match_list_length_pctls.len());
+    assert!(stats
+        .top_ac_match_list_lengths
+        .windows(2)
+        .all(|w| w[0] >= w[1]));
+}
+
 #[test]
 fn test_multithread() {
     use crossbeam::scope;
@@ -462,6 +911,55 @@ fn test_multithread() {
     .unwrap();
 }
 
+#[test]
+fn test_walk() {
+    use std::fs;
+    use yara::walk::Walker;
+
+    let dir = tempfile::tempdir().expect("should create temp dir");
+    fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+    fs::create_dir_all(dir.path().join("target")).unwrap();
+    fs::write(dir.path().join("a.txt"), "rust").unwrap();
+    fs::write(dir.path().join("b.bin"), "rust").unwrap();
+    fs::write(dir.path().join("src/nested/c.txt"), "go").unwrap();
+    fs::write(dir.path().join("src/large.txt"), "rust".repeat(100)).unwrap();
+    fs::write(dir.path().join("target/d.txt"), "rust").unwrap();
+
+    let mut results: Vec<(String, Vec<String>)> = Walker::new(get_default_rules(), [dir.path()])
+        .workers(3)
+        .include("*.txt")
+        .exclude("target")
+        .max_file_size(100)
+        .walk()
+        .map(|file| {
+            let path = file.path.strip_prefix(dir.path()).unwrap();
+            let rules = file.result.expect("should have scanned");
+            (
+                path.to_string_lossy().replace('\\', "/"),
+                rules.into_iter().map(|rule| rule.identifier).collect(),
+            )
+        })
+        .collect();
+    results.sort();
+
+    assert_eq!(
+        results,
+        [
+            ("a.txt".to_string(), vec!["is_awesome".to_string()]),
+            ("src/nested/c.txt".to_string(), vec!["is_ok".to_string()]),
+        ]
+    );
+
+    let mut walk = Walker::new(get_default_rules(), [dir.path().join("missing")]).walk();
+    let file = walk.next().expect("should report the missing root");
+    assert_eq!(dir.path().join("missing"), file.path);
+    match file.result {
+        Err(Error::Io(error)) => assert_eq!(&IoErrorKind::OpeningScanRoot, error.kind()),
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    assert!(walk.next().is_none());
+}
+
 #[test]
 fn test_rule_load_save_mem() {
     let mut rules = get_default_rules();
@@ -507,6 +1005,31 @@ rule is_slow
     compile(rule);
 }
 
+#[test]
+fn test_compile_warnings() {
+    let rule = r#"
+rule is_slow
+{
+    strings:
+        $re1 = /state:.*(on|off)/
+
+    condition:
+        $re1
+}
+"#;
+
+    let compiler = Compiler::new().unwrap().add_rules_str(rule).unwrap();
+    assert_eq!(1, compiler.warnings().len());
+
+    let (_rules, warnings) = compiler
+        .compile_rules_with_warnings()
+        .expect("Should compile rules");
+    assert_eq!(1, warnings.len());
+    let warning = &warnings[0];
+    assert_eq!(CompileErrorLevel::Warning, warning.level);
+    assert!(warning.message.contains("$re1"));
+}
+
 fn test_default_rules(rules: &Rules) {
     let scan_mem_result = rules.scan_mem("I love Rust!".as_bytes(), 10);
     let scan_result = scan_mem_result.expect("Should be Ok");
@@ -695,3 +1218,459 @@ fn test_callback_module_imported() {
         "should have add a ModuleImported callback msg"
     );
 }
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serialize_module_data() {
+    let rules = get_default_rules();
+    let mut module_data = None;
+
+    rules
+        .scan_mem_callback(b"", 1, |callback_msg| {
+            if let CallbackMsg::ModuleImported(module) = callback_msg {
+                module_data = Some(serde_json::to_value(&module).expect("should serialize"));
+            }
+            CallbackReturn::Continue
+        })
+        .expect("should scan");
+
+    let pe = module_data.expect("should have imported pe");
+    assert_eq!(pe["IMPORT_STANDARD"], 1);
+    assert_eq!(pe["dll_name"], serde_json::Value::Null);
+    assert_eq!(pe["sections"], serde_json::json!([]));
+    assert!(pe["version_info"].is_object());
+    // Functions are not serialized.
+    assert!(pe.get("section_index").is_none());
+}
+
+/// Build a little-endian ELF64 executable with a section named `name`, followed by the
+/// section names table.
+fn elf_with_section(name: &[u8]) -> Vec<u8> {
+    let mut names = vec![0];
+    names.extend_from_slice(name);
+    names.push(0);
+    let names_offset = names.len() as u32;
+    names.extend_from_slice(b".shstrtab\0");
+
+    let mut elf = vec![0; 64];
+    elf[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+    elf[16..18].copy_from_slice(&2u16.to_le_bytes());
+    elf[18..20].copy_from_slice(&0x3eu16.to_le_bytes());
+    elf[20..24].copy_from_slice(&1u32.to_le_bytes());
+    elf[40..48].copy_from_slice(&64u64.to_le_bytes());
+    elf[52..54].copy_from_slice(&64u16.to_le_bytes());
+    elf[58..60].copy_from_slice(&64u16.to_le_bytes());
+    elf[60..62].copy_from_slice(&2u16.to_le_bytes());
+    elf[62..64].copy_from_slice(&1u16.to_le_bytes());
+
+    let mut section = vec![0; 64];
+    section[..4].copy_from_slice(&1u32.to_le_bytes());
+    section[4..8].copy_from_slice(&1u32.to_le_bytes());
+    elf.extend(section);
+    let mut names_section = vec![0; 64];
+    names_section[..4].copy_from_slice(&names_offset.to_le_bytes());
+    names_section[4..8].copy_from_slice(&3u32.to_le_bytes());
+    names_section[24..32].copy_from_slice(&192u64.to_le_bytes());
+    names_section[32..40].copy_from_slice(&(names.len() as u64).to_le_bytes());
+    elf.extend(names_section);
+    elf.extend(names);
+    elf
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_serialize_raw_string() {
+    let rules =
+        compile("import \"elf\" rule has_sections { condition: elf.number_of_sections == 2 }");
+    let elf_data = |name: &[u8]| {
+        let mut module_data = None;
+        rules
+            .scan_mem_callback(&elf_with_section(name), 1, |callback_msg| {
+                if let CallbackMsg::ModuleImported(module) = callback_msg {
+                    module_data = Some(serde_json::to_value(&module).expect("should serialize"));
+                }
+                CallbackReturn::Continue
+            })
+            .expect("should scan");
+        module_data.expect("should have imported elf")
+    };
+
+    let elf = elf_data(b"a\"\\b\x01");
+    assert_eq!(elf["sections"][0]["name"], "a\"\\b\u{1}");
+    assert_eq!(elf["sections"][1]["name"], ".shstrtab");
+    // Strings which are not valid UTF-8 are serialized as bytes.
+    let elf = elf_data(b"a\xff");
+    assert_eq!(elf["sections"][0]["name"], serde_json::json!([97, 255]));
+}
+
+#[test]
+fn test_module_data_get() {
+    let rules = get_default_rules();
+    let mut found_module = false;
+
+    rules
+        .scan_mem_callback(b"", 1, |callback_msg| {
+            if let CallbackMsg::ModuleImported(pe) = callback_msg {
+                found_module = true;
+                let import_standard = pe.get("IMPORT_STANDARD").expect("should exist");
+                assert_eq!(import_standard.as_i64(), Some(1));
+                assert_eq!(import_standard.as_f64(), None);
+                assert_eq!(
+                    pe.get("pe.IMPORT_STANDARD").and_then(|o| o.as_i64()),
+                    Some(1)
+                );
+                assert_eq!(pe.get("dll_name").expect("should exist").as_bytes(), None);
+                assert_eq!(pe.get("sections").unwrap().iter_array().unwrap().count(), 0);
+                assert!(pe.get("sections[0].name").is_none());
+                assert_eq!(
+                    pe.get("version_info").unwrap().iter_dict().unwrap().count(),
+                    0
+                );
+                assert!(pe.get("version_info[\"CompanyName\"]").is_none());
+                assert!(pe.get("version_info").unwrap().iter_array().is_none());
+                assert!(pe.get("non_existing_key").is_none());
+            }
+            CallbackReturn::Continue
+        })
+        .expect("should scan");
+
+    assert!(found_module);
+}
+
+#[test]
+fn test_module_data_get_values() {
+    let rules = compile("import \"elf\" import \"tests\" rule r { condition: true }");
+    let mut modules = Vec::new();
+
+    rules
+        .scan_mem_callback(&elf_with_section(b".text"), 1, |callback_msg| {
+            if let CallbackMsg::ModuleImported(module) = callback_msg {
+                let name = module.identifier().unwrap().to_vec();
+                modules.push(name.clone());
+                if name == b"elf" {
+                    assert_eq!(module.get("number_of_sections").unwrap().as_i64(), Some(2));
+                    assert_eq!(
+                        module.get("sections[0].name").unwrap().as_bytes(),
+                        Some(b".text".as_slice())
+                    );
+                    let names: Vec<_> = module
+                        .get("sections")
+                        .unwrap()
+                        .iter_array()
+                        .unwrap()
+                        .map(|section| section.unwrap().get("name").unwrap().as_bytes())
+                        .collect();
+                    assert_eq!(
+                        names,
+                        [Some(b".text".as_slice()), Some(b".shstrtab".as_slice())]
+                    );
+                    assert!(module.get("sections[2]").is_none());
+                } else {
+                    assert_eq!(module.get("constants.one").unwrap().as_i64(), Some(1));
+                    assert_eq!(module.get("struct_array[1].i").unwrap().as_i64(), Some(1));
+                    assert_eq!(
+                        module.get("string_dict[\"foo\"]").unwrap().as_bytes(),
+                        Some(b"foo".as_slice())
+                    );
+                    let dict: HashMap<_, _> = module
+                        .get("string_dict")
+                        .unwrap()
+                        .iter_dict()
+                        .unwrap()
+                        .map(|(key, value)| (key, value.as_bytes()))
+                        .collect();
+                    assert_eq!(dict.get(b"foo".as_slice()), Some(&Some(b"foo".as_slice())));
+                    assert_eq!(dict.get(b"bar".as_slice()), Some(&Some(b"bar".as_slice())));
+                }
+            }
+            CallbackReturn::Continue
+        })
+        .expect("should scan");
+
+    modules.sort();
+    assert_eq!(modules, [b"elf".to_vec(), b"tests".to_vec()]);
+}
+
+#[test]
+fn test_scan_with_module_data() {
+    let rules = compile(
+        r#"
+import "pe"
+import "math"
+
+rule contains_rust {
+  strings:
+    $rust = "rust"
+  condition:
+    $rust or pe.is_dll()
+}
+"#,
+    );
+    let mut scanner = rules.scanner().unwrap();
+
+    let results = scanner.scan_mem_owned(b"rust", &ScanOptions::new());
+    assert!(results.is_complete());
+    assert_eq!(results.matches.len(), 1);
+    assert!(results.modules.is_empty());
+
+    let options = ScanOptions::new().capture_modules(["pe", "elf"]);
+    let results = scanner.scan_mem_owned(b"rust", &options);
+    assert!(results.is_complete());
+    assert_eq!(results.matches[0].identifier, "contains_rust");
+    assert_eq!(results.modules.len(), 1);
+    let pe = &results.modules[0];
+    assert_eq!(pe.name, "pe");
+    let members = match &pe.value {
+        OwnedYrObjectValue::Structure(members) => members,
+        value => panic!("pe should be a structure, not {value:?}"),
+    };
+    let member = |name: &str| {
+        members
+            .iter()
+            .find(|(identifier, _)| identifier == name)
+            .map(|(_, value)| value)
+    };
+    assert_eq!(
+        member("IMPORT_STANDARD"),
+        Some(&OwnedYrObjectValue::Integer(1))
+    );
+    // Functions are not captured.
+    assert_eq!(member("is_dll"), None);
+    assert_eq!(member("sections"), Some(&OwnedYrObjectValue::Array(vec![])));
+
+    let reader = std::io::Cursor::new(b"rust".to_vec());
+    let blocks_results =
+        scanner.scan_mem_blocks_owned(yara::ReaderMemoryBlockIterator::new(reader), &options);
+    assert!(blocks_results.is_complete());
+    assert_eq!(blocks_results.matches, results.matches);
+    assert_eq!(blocks_results.modules, results.modules);
+}
+
+#[test]
+#[cfg(feature = "module-cuckoo")]
+fn test_scanner_set_module_data() {
+    let rules = compile(
+        r#"
+import "cuckoo"
+
+rule contacts_example {
+  condition:
+    cuckoo.network.http_request(/example\.com/)
+}
+"#,
+    );
+    let mut scanner = rules.scanner().unwrap();
+
+    let results = scanner.scan_mem(b"").expect("should scan");
+    assert!(results.is_empty());
+
+    scanner.set_module_data(
+        "cuckoo",
+        r#"{"network": {"http": [{"uri": "http://example.com/", "method": "GET"}]}}"#,
+    );
+    let results = scanner.scan_mem(b"").expect("should scan");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].identifier, "contacts_example");
+
+    scanner.remove_module_data("cuckoo");
+    let results = scanner.scan_mem(b"").expect("should scan");
+    assert!(results.is_empty());
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn test_archive_scanner() {
+    use std::ffi::CString;
+    use std::io::{Cursor, Write};
+
+    use yara::archive::ArchiveScanner;
+    use yara::ArchiveError;
+
+    let rules = compile("rule is_rust { strings: $rust = \"rust\" condition: $rust }");
+
+    let mut tar = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(7);
+    header.set_cksum();
+    tar.append_data(&mut header, "docs/a.txt", &b"rust ok"[..])
+        .unwrap();
+    let tar = tar.into_inner().unwrap();
+
+    let gzip_header = libflate::gzip::HeaderBuilder::new()
+        .filename(CString::new("docs.tar").unwrap())
+        .finish();
+    let options = libflate::gzip::EncodeOptions::new().header(gzip_header);
+    let mut gzip = libflate::gzip::Encoder::with_options(Vec::new(), options).unwrap();
+    gzip.write_all(&tar).unwrap();
+    let gzip = gzip.finish().into_result().unwrap();
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default();
+    zip.start_file("docs.tar.gz", options).unwrap();
+    zip.write_all(&gzip).unwrap();
+    zip.start_file("b.txt", options).unwrap();
+    zip.write_all(b"nothing").unwrap();
+    let zip = zip.finish().unwrap().into_inner();
+
+    let mut scanner = rules.scanner().unwrap();
+    let members = ArchiveScanner::new(&mut scanner).scan_mem(&zip);
+    let paths: Vec<_> = members.iter().map(|m| m.path.join("/")).collect();
+    assert_eq!(
+        paths,
+        [
+            "",
+            "docs.tar.gz",
+            "docs.tar.gz/docs.tar",
+            "docs.tar.gz/docs.tar/docs/a.txt",
+            "b.txt"
+        ]
+    );
+    let matches: Vec<_> = members
+        .iter()
+        .map(|m| !m.result.as_ref().unwrap().is_empty())
+        .collect();
+    assert_eq!(matches, [false, false, true, true, false]);
+
+    let members = ArchiveScanner::new(&mut scanner)
+        .max_depth(1)
+        .scan_mem(&zip);
+    assert_eq!(3, members.len());
+
+    let members = ArchiveScanner::new(&mut scanner)
+        .max_member_size(7)
+        .scan_mem(&zip);
+    assert_eq!(members[1].path, ["docs.tar.gz"]);
+    assert!(matches!(
+        members[1].result,
+        Err(Error::Archive(ArchiveError::MemberTooLarge(7)))
+    ));
+    assert_eq!(members[2].path, ["b.txt"]);
+    assert!(members[2].result.is_ok());
+
+    // A highly compressible member, above the size under which the ratio is not checked.
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("zeros.bin", options).unwrap();
+    zip.write_all(&vec![0; 2 * 1024 * 1024]).unwrap();
+    zip.start_file("c.txt", options).unwrap();
+    zip.write_all(b"rust").unwrap();
+    let bomb = zip.finish().unwrap().into_inner();
+
+    let members = ArchiveScanner::new(&mut scanner).scan_mem(&bomb);
+    assert_eq!(members[1].path, ["zeros.bin"]);
+    assert!(matches!(
+        members[1].result,
+        Err(Error::Archive(ArchiveError::RatioExceeded(100)))
+    ));
+    assert_eq!(members[2].path, ["c.txt"]);
+    assert!(!members[2].result.as_ref().unwrap().is_empty());
+
+    let members = ArchiveScanner::new(&mut scanner)
+        .max_ratio(u64::MAX)
+        .scan_mem(&bomb);
+    assert!(members[1].result.is_ok());
+
+    // The members after the one exceeding the total size are not scanned.
+    let members = ArchiveScanner::new(&mut scanner)
+        .max_ratio(u64::MAX)
+        .max_total_size(2 * 1024 * 1024 + 2)
+        .scan_mem(&bomb);
+    assert_eq!(3, members.len());
+    assert!(members[1].result.is_ok());
+    assert_eq!(members[2].path, ["c.txt"]);
+    assert!(matches!(
+        members[2].result,
+        Err(Error::Archive(ArchiveError::TotalSizeExceeded(2_097_154)))
+    ));
+}
+
+#[test]
+fn test_scan_elf_core() {
+    use yara::dump::ElfCoreMemoryIterator;
+
+    assert!(matches!(
+        ElfCoreMemoryIterator::new(b"not a core"),
+        Err(Error::Io(e)) if e.kind() == &IoErrorKind::ParsingDump
+    ));
+}
+
+#[test]
+fn test_scan_minidump() {
+    use yara::dump::MinidumpMemoryIterator;
+
+    assert!(matches!(
+        MinidumpMemoryIterator::new(b"MDMP"),
+        Err(Error::Io(e)) if e.kind() == &IoErrorKind::ParsingDump
+    ));
+}
+
+#[test]
+fn test_rule_selector() {
+    use yara::{RuleSelector, RulesEnablement};
+
+    let mut rules = Compiler::new()
+        .unwrap()
+        .add_rules_str(
+            r#"
+rule APT_Loader : apt {
+  meta:
+    severity = 8
+    author = "x"
+  condition:
+    true
+}
+
+rule APT_Dropper : apt {
+  meta:
+    severity = 3
+  condition:
+    true
+}"#,
+        )
+        .unwrap()
+        .add_rules_str_with_namespace("rule Generic { condition: true }", "generic")
+        .unwrap()
+        .compile_rules()
+        .unwrap();
+    let matching = |rules: &Rules| -> Vec<String> {
+        let mut identifiers: Vec<_> = rules
+            .scan_mem(b"", 10)
+            .unwrap()
+            .iter()
+            .map(|rule| rule.identifier.to_owned())
+            .collect();
+        identifiers.sort();
+        identifiers
+    };
+
+    let selector = RuleSelector::new()
+        .identifier("APT_*")
+        .tag("apt")
+        .metadata("meta.severity >= 5".parse().unwrap());
+    let selected = selector.select(&rules);
+    assert_eq!(1, selected.len());
+    assert_eq!("APT_Loader", selected[0].identifier);
+
+    let mut dropper = rules
+        .get_rules()
+        .into_iter()
+        .find(|rule| rule.identifier == "APT_Dropper")
+        .unwrap();
+    dropper.disable();
+    let initial = RulesEnablement::snapshot(&rules);
+
+    let previous = selector.apply(&mut rules);
+    assert_eq!(["APT_Loader"], matching(&rules)[..]);
+    RuleSelector::new().namespace("generic").apply(&mut rules);
+    assert_eq!(["Generic"], matching(&rules)[..]);
+    RuleSelector::new()
+        .metadata(r#"author == "x""#.parse().unwrap())
+        .apply(&mut rules);
+    assert_eq!(["APT_Loader"], matching(&rules)[..]);
+
+    previous.restore(&mut rules);
+    assert_eq!(["APT_Loader", "Generic"], matching(&rules)[..]);
+    RuleSelector::new().apply(&mut rules);
+    assert_eq!(3, matching(&rules).len());
+    initial.restore(&mut rules);
+    assert_eq!(["APT_Loader", "Generic"], matching(&rules)[..]);
+}
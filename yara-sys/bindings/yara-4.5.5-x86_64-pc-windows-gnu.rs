@@ -2834,12 +2834,43 @@ pub const ERROR_BIDI_NO_LOCALIZED_RESOURCES: u32 = 13015;
 pub const ERROR_BIDI_NO_BIDI_SCHEMA_EXTENSIONS: u32 = 13016;
 pub const ERROR_BIDI_UNSUPPORTED_CLIENT_LANGUAGE: u32 = 13017;
 pub const ERROR_BIDI_UNSUPPORTED_RESOURCE_FORMAT: u32 = 13018;
+pub const RULE_FLAGS_PRIVATE: u32 = 1;
+pub const RULE_FLAGS_GLOBAL: u32 = 2;
 pub const RULE_FLAGS_NULL: u32 = 4;
+pub const RULE_FLAGS_DISABLED: u32 = 8;
+pub const STRING_FLAGS_REFERENCED: u32 = 1;
+pub const STRING_FLAGS_HEXADECIMAL: u32 = 2;
+pub const STRING_FLAGS_NO_CASE: u32 = 4;
+pub const STRING_FLAGS_ASCII: u32 = 8;
+pub const STRING_FLAGS_WIDE: u32 = 16;
+pub const STRING_FLAGS_REGEXP: u32 = 32;
+pub const STRING_FLAGS_FAST_REGEXP: u32 = 64;
+pub const STRING_FLAGS_FULL_WORD: u32 = 128;
+pub const STRING_FLAGS_ANONYMOUS: u32 = 256;
+pub const STRING_FLAGS_SINGLE_MATCH: u32 = 512;
+pub const STRING_FLAGS_LITERAL: u32 = 1024;
+pub const STRING_FLAGS_FITS_IN_ATOM: u32 = 2048;
 pub const STRING_FLAGS_LAST_IN_RULE: u32 = 4096;
+pub const STRING_FLAGS_CHAIN_PART: u32 = 8192;
+pub const STRING_FLAGS_CHAIN_TAIL: u32 = 16384;
+pub const STRING_FLAGS_FIXED_OFFSET: u32 = 32768;
+pub const STRING_FLAGS_GREEDY_REGEXP: u32 = 65536;
+pub const STRING_FLAGS_DOT_ALL: u32 = 131072;
+pub const STRING_FLAGS_DISABLED: u32 = 262144;
+pub const STRING_FLAGS_XOR: u32 = 524288;
+pub const STRING_FLAGS_PRIVATE: u32 = 1048576;
+pub const STRING_FLAGS_BASE64: u32 = 2097152;
+pub const STRING_FLAGS_BASE64_WIDE: u32 = 4194304;
 pub const META_TYPE_INTEGER: u32 = 1;
 pub const META_TYPE_STRING: u32 = 2;
 pub const META_TYPE_BOOLEAN: u32 = 3;
 pub const META_FLAGS_LAST_IN_RULE: u32 = 1;
+pub const EXTERNAL_VARIABLE_TYPE_NULL: u32 = 0;
+pub const EXTERNAL_VARIABLE_TYPE_FLOAT: u32 = 1;
+pub const EXTERNAL_VARIABLE_TYPE_INTEGER: u32 = 2;
+pub const EXTERNAL_VARIABLE_TYPE_BOOLEAN: u32 = 3;
+pub const EXTERNAL_VARIABLE_TYPE_STRING: u32 = 4;
+pub const EXTERNAL_VARIABLE_TYPE_MALLOC_STRING: u32 = 5;
 pub const YARA_ERROR_LEVEL_ERROR: u32 = 0;
 pub const YARA_ERROR_LEVEL_WARNING: u32 = 1;
 pub const ERROR_INSUFICIENT_MEMORY: u32 = 1;
@@ -6529,6 +6560,30 @@ fn bindgen_test_layout_YR_OBJECT_STRUCTURE() {
         )
     );
 }
+pub type YR_MODULE_FUNC = ::std::option::Option<
+    unsafe extern "C" fn(
+        __args: *mut YR_VALUE,
+        __context: *mut YR_SCAN_CONTEXT,
+        __function_obj: *mut YR_OBJECT_FUNCTION,
+    ) -> ::std::os::raw::c_int,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct YR_OBJECT_FUNCTION {
+    pub canary: ::std::os::raw::c_int,
+    pub type_: i8,
+    pub identifier: *const ::std::os::raw::c_char,
+    pub parent: *mut YR_OBJECT,
+    pub data: *mut ::std::os::raw::c_void,
+    pub return_obj: *mut YR_OBJECT,
+    pub prototypes: [YR_OBJECT_FUNCTION__bindgen_ty_1; 10usize],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct YR_OBJECT_FUNCTION__bindgen_ty_1 {
+    pub arguments_fmt: *const ::std::os::raw::c_char,
+    pub code: YR_MODULE_FUNC,
+}
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct YR_OBJECT_ARRAY {
@@ -7813,6 +7868,52 @@ fn bindgen_test_layout__YR_COMPILER() {
     );
 }
 pub type YR_COMPILER = _YR_COMPILER;
+extern "C" {
+    pub fn yr_free(ptr: *mut ::std::os::raw::c_void);
+}
+extern "C" {
+    pub fn yr_object_create(
+        type_: i8,
+        identifier: *const ::std::os::raw::c_char,
+        parent: *mut YR_OBJECT,
+        object: *mut *mut YR_OBJECT,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn yr_object_function_create(
+        identifier: *const ::std::os::raw::c_char,
+        arguments_fmt: *const ::std::os::raw::c_char,
+        return_fmt: *const ::std::os::raw::c_char,
+        func: YR_MODULE_FUNC,
+        parent: *mut YR_OBJECT,
+        function: *mut *mut YR_OBJECT,
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn yr_object_set_integer(
+        value: i64,
+        object: *mut YR_OBJECT,
+        field: *const ::std::os::raw::c_char,
+        ...
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn yr_object_set_float(
+        value: f64,
+        object: *mut YR_OBJECT,
+        field: *const ::std::os::raw::c_char,
+        ...
+    ) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn yr_object_set_string(
+        value: *const ::std::os::raw::c_char,
+        len: size_t,
+        object: *mut YR_OBJECT,
+        field: *const ::std::os::raw::c_char,
+        ...
+    ) -> ::std::os::raw::c_int;
+}
 extern "C" {
     pub fn yr_compiler_create(compiler: *mut *mut YR_COMPILER) -> ::std::os::raw::c_int;
 }
@@ -28,7 +28,7 @@ pub fn get_target_env_var(env_var: &str) -> Option<String> {
 mod build {
     use fs_extra::dir::{copy, CopyOptions};
 
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     use super::cargo_rerun_if_env_changed;
     use super::get_target_env_var;
@@ -124,8 +124,27 @@ mod build {
         }
     }
 
+    /// Add the modules implemented in Rust, listed in `YARA_RUST_MODULES`, to the
+    /// modules table of libyara.
+    ///
+    /// The list is always rebuilt from the original one, to drop the modules removed
+    /// from the variable since the last build.
+    fn add_rust_modules(original_list: &Path, module_list: &Path) {
+        let mut list = std::fs::read_to_string(original_list).unwrap();
+        if let Some(modules) = get_target_env_var("YARA_RUST_MODULES") {
+            for module in modules.split(',').map(str::trim).filter(|m| !m.is_empty()) {
+                list.push_str(&format!("MODULE({module})\n"));
+            }
+        }
+        std::fs::write(module_list, list).unwrap();
+    }
+
     pub fn build_and_link() {
         let old_basedir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("yara");
+        let original_module_list = old_basedir
+            .join("libyara")
+            .join("modules")
+            .join("module_list");
         // check the yara source folder is not empty
         if old_basedir
             .read_dir()
@@ -150,6 +169,10 @@ mod build {
             copy(old_basedir, &basedir, &opt).unwrap();
         }
         let basedir = basedir.join("libyara");
+        add_rust_modules(
+            &original_module_list,
+            &basedir.join("modules").join("module_list"),
+        );
 
         let mut cc = cc::Build::new();
         cc.include(&basedir)
@@ -323,6 +346,7 @@ mod build {
         cargo_rerun_if_env_changed("YARA_OPENSSL_INCLUDE_DIR");
         cargo_rerun_if_env_changed("YARA_LIBRARY_PATH");
         cargo_rerun_if_env_changed("YARA_CRYPTO_LIB");
+        cargo_rerun_if_env_changed("YARA_RUST_MODULES");
 
         println!("cargo:rustc-link-search=native={lib_dir}");
         println!("cargo:rustc-link-lib=static=yara");
@@ -398,11 +422,12 @@ mod bindings {
             .header("wrapper.h")
             .allowlist_var("CALLBACK_.*")
             .allowlist_var("ERROR_.*")
+            .allowlist_var("EXTERNAL_VARIABLE_TYPE_.*")
             .allowlist_var("META_TYPE_.*")
             .allowlist_var("META_FLAGS_LAST_IN_RULE")
             .allowlist_var("OBJECT_TYPE_.*")
-            .allowlist_var("STRING_FLAGS_LAST_IN_RULE")
-            .allowlist_var("RULE_FLAGS_NULL")
+            .allowlist_var("STRING_FLAGS_.*")
+            .allowlist_var("RULE_FLAGS_.*")
             .allowlist_var("YARA_ERROR_LEVEL_.*")
             .allowlist_var("SCAN_FLAGS_.*")
             .allowlist_var("YR_CONFIG_.*")
@@ -418,6 +443,10 @@ mod bindings {
             .allowlist_function("yr_initialize")
             .allowlist_function("yr_finalize")
             .allowlist_function("yr_finalize_thread")
+            .allowlist_function("yr_free")
+            .allowlist_function("yr_object_create")
+            .allowlist_function("yr_object_function_create")
+            .allowlist_function("yr_object_set_.*")
             .allowlist_function("yr_compiler_.*")
             .allowlist_function("yr_rule_.*")
             .allowlist_function("yr_rules_.*")
@@ -428,6 +457,7 @@ mod bindings {
             .allowlist_type("YR_OBJECT_STRUCTURE")
             .allowlist_type("YR_OBJECT_ARRAY")
             .allowlist_type("YR_OBJECT_DICTIONARY")
+            .allowlist_type("YR_OBJECT_FUNCTION")
             .allowlist_type("YR_RULES")
             // XXX: Ideally, YR_COMPILER would be marked as opaque. Unfortunately, because it
             // contains a jmp_buf that is, on x64 windows msvc, aligned on 16-bytes, this generates
@@ -79,6 +79,29 @@ impl Error {
             _ => Unknown(code),
         })
     }
+
+    /// Convert back to the Yara error code.
+    pub fn to_code(self) -> c_int {
+        use self::Error::*;
+
+        let code = match self {
+            CallbackError => ERROR_CALLBACK_ERROR,
+            CorruptFile => ERROR_CORRUPT_FILE,
+            CouldNotAttach => ERROR_COULD_NOT_ATTACH_TO_PROCESS,
+            CouldNotMapFile => ERROR_COULD_NOT_MAP_FILE,
+            CouldNotOpenFile => ERROR_COULD_NOT_OPEN_FILE,
+            InsufficientMemory => ERROR_INSUFFICIENT_MEMORY,
+            InternalFatalError => ERROR_INTERNAL_FATAL_ERROR,
+            InvalidFile => ERROR_INVALID_FILE,
+            ScanTimeout => ERROR_SCAN_TIMEOUT,
+            SyntaxError => ERROR_SYNTAX_ERROR,
+            TooManyMatches => ERROR_TOO_MANY_MATCHES,
+            UnsupportedFileVersion => ERROR_UNSUPPORTED_FILE_VERSION,
+            CouldNotReadProcessMemory => ERROR_COULD_NOT_READ_PROCESS_MEMORY,
+            Unknown(code) => return code,
+        };
+        code as c_int
+    }
 }
 
 impl fmt::Display for Error {
@@ -131,6 +154,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_error_to_code() {
+        use super::Error::*;
+
+        for code in [ERROR_INSUFFICIENT_MEMORY, ERROR_SCAN_TIMEOUT, 1000] {
+            let error = Error::from_code(code as i32).unwrap_err();
+            assert_eq!(code as i32, error.to_code());
+        }
+        assert_eq!(ERROR_CALLBACK_ERROR as i32, CallbackError.to_code());
+    }
+
     #[test]
     fn test_to_string() {
         assert_eq!(
@@ -41,6 +41,28 @@ impl MetaType {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExternalVariableType {
+    Integer,
+    Float,
+    Boolean,
+    String,
+}
+
+impl ExternalVariableType {
+    #[deny(unused_variables)]
+    pub fn from_code(code: i32) -> Result<Self, i32> {
+        use self::ExternalVariableType::*;
+        match code as u32 {
+            EXTERNAL_VARIABLE_TYPE_INTEGER => Ok(Integer),
+            EXTERNAL_VARIABLE_TYPE_FLOAT => Ok(Float),
+            EXTERNAL_VARIABLE_TYPE_BOOLEAN => Ok(Boolean),
+            EXTERNAL_VARIABLE_TYPE_STRING | EXTERNAL_VARIABLE_TYPE_MALLOC_STRING => Ok(String),
+            _ => Err(code),
+        }
+    }
+}
+
 impl YR_MATCHES {
     #[deprecated = "Useless now"]
     pub fn get_head(&self) -> *const YR_MATCH {
@@ -64,6 +86,12 @@ impl YR_META {
     }
 }
 
+impl YR_EXTERNAL_VARIABLE {
+    pub fn get_identifier(&self) -> *const c_char {
+        unsafe { self.__bindgen_anon_1.identifier }
+    }
+}
+
 impl YR_NAMESPACE {
     pub fn get_name(&self) -> *const c_char {
         unsafe { self.__bindgen_anon_1.name }
@@ -74,6 +102,10 @@ impl YR_RULES {
     pub fn get_rules_table(&self) -> *const YR_RULE {
         unsafe { self.__bindgen_anon_1.rules_table }
     }
+
+    pub fn get_ext_vars_table(&self) -> *const YR_EXTERNAL_VARIABLE {
+        unsafe { self.__bindgen_anon_3.ext_vars_table }
+    }
 }
 
 impl YR_RULE {